@@ -1,23 +1,43 @@
 use crate::{
-    length::Length,
+    length::{Length, MAX_LENGTH},
     mask::{ARRAY_MASK, TYPE_MASK},
+    options::Context,
+    parser::ParseError,
     skip_zeroes::skip_zeroes,
-    tlv::{bitmix_consume_byte, BitmixToTLV, DecodeTLV},
+    tlv::{bitmix_close_container, bitmix_consume_byte, BitmixToTLV, DecodeTLV},
     value::Value,
     ws::skip_ws,
 };
 
-#[derive(Debug)]
+#[derive(Clone, Copy)]
 pub struct Array<'a> {
     pub(crate) data: &'a [u8],
+    pub(crate) length: usize,
+    // position of the opening bracket in the input of `Parser::parse`,
+    // `None` when the value was decoded from TLV that can't be traced back to it
+    pub(crate) offset: Option<usize>,
 }
 
-fn bitmix_element(data: &mut [u8], pos: &mut usize) -> Option<()> {
-    Value::bitmix_to_tlv(data, pos)?;
+fn bitmix_element(
+    data: &mut [u8],
+    pos: &mut usize,
+    length: &mut usize,
+    ctx: &mut Context,
+) -> Option<()> {
+    if *length >= ctx.options.max_elements.min(MAX_LENGTH - 1) {
+        return ctx.fail(ParseError::TooManyElements);
+    }
+    Value::bitmix_to_tlv_with(data, pos, ctx)?;
+    *length += 1;
     Some(())
 }
 
-fn bitmix_elements_and_close(data: &mut [u8], pos: &mut usize, length: &mut usize) -> Option<()> {
+fn bitmix_elements_and_close(
+    data: &mut [u8],
+    pos: &mut usize,
+    length: &mut usize,
+    ctx: &mut Context,
+) -> Option<()> {
     skip_ws(data, pos);
 
     if bitmix_consume_byte::<b']'>(data, pos) {
@@ -25,8 +45,7 @@ fn bitmix_elements_and_close(data: &mut [u8], pos: &mut usize, length: &mut usiz
         return Some(());
     }
 
-    bitmix_element(data, pos)?;
-    *length += 1;
+    bitmix_element(data, pos, length, ctx)?;
 
     while *pos < data.len() {
         skip_ws(data, pos);
@@ -35,37 +54,44 @@ fn bitmix_elements_and_close(data: &mut [u8], pos: &mut usize, length: &mut usiz
             return Some(());
         } else if bitmix_consume_byte::<b','>(data, pos) {
             skip_ws(data, pos);
-            bitmix_element(data, pos)?;
-            *length += 1;
+            bitmix_element(data, pos, length, ctx)?;
+        } else {
+            return None;
         }
     }
 
     None
 }
 
+impl Array<'_> {
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+}
+
 impl BitmixToTLV for Array<'_> {
-    fn bitmix_to_tlv(data: &mut [u8], pos: &mut usize) -> Option<()> {
+    fn bitmix_to_tlv_with(data: &mut [u8], pos: &mut usize, ctx: &mut Context) -> Option<()> {
         let start = *pos;
         let mut length = 0;
 
-        if data[*pos] != b'[' {
+        if data.get(*pos) != Some(&b'[') {
             return None;
         }
+        ctx.enter()?;
 
         *pos += 1;
         skip_ws(data, pos);
 
         if !bitmix_consume_byte::<b']'>(data, pos) {
-            bitmix_elements_and_close(data, pos, &mut length)?;
+            bitmix_elements_and_close(data, pos, &mut length, ctx)?;
         }
+        ctx.leave();
 
-        data[start] = 0;
-        data[*pos - 1] = 0;
-
-        Length::write(data, start, *pos, length);
-        data[start] |= ARRAY_MASK;
-
-        Some(())
+        bitmix_close_container(data, start, *pos, length, ARRAY_MASK)
     }
 }
 
@@ -73,7 +99,7 @@ impl<'a> DecodeTLV<'a> for Array<'a> {
     type ReturnType = Self;
 
     fn decode_tlv(data: &'a [u8], pos: &mut usize) -> Option<Self::ReturnType> {
-        if *pos >= data.len() {
+        if *pos + 1 >= data.len() {
             return None;
         }
         if data[*pos] & TYPE_MASK != ARRAY_MASK {
@@ -86,21 +112,23 @@ impl<'a> DecodeTLV<'a> for Array<'a> {
         let start = *pos;
         for _ in 0..length {
             skip_zeroes(data, pos);
-            let at = *pos;
             if !Value::skip_tlv(data, pos) {
-                panic!("invalid array element at {}: {:?}", at, &data[at..]);
+                return None;
             }
         }
         let end = *pos;
 
         let result = Array {
             data: &data[start..end],
+            length,
+            offset: None,
         };
         Some(result)
     }
 }
 
 #[test]
+#[allow(clippy::identity_op)] // the length bits are spelled out
 fn test_array_empty() {
     let mut pos = 1;
     let mut data = *b" []";
@@ -0,0 +1,437 @@
+use core::{
+    cmp::Ordering,
+    fmt::{self, Write},
+};
+
+use crate::{
+    array::Array, object::Object, serialize::write_string, skip_zeroes::skip_zeroes,
+    string::String, tlv::DecodeTLV, value::Value,
+};
+
+// RFC 8785 JSON Canonicalization Scheme: no whitespace, members sorted by the UTF-16 code units
+// of their keys, numbers as ECMAScript's `Number.prototype.toString` writes them
+// and strings with only the escapes that JSON requires.
+// Members of an object are sorted in scratch as their positions in its data,
+// nested objects take the slots after the ones of their parent
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanonicalError {
+    /// Scratch has fewer slots than `Value::canonical_scratch_len`,
+    /// or an object is too large for positions in it to fit in `u32`.
+    ScratchTooSmall,
+    /// A string or a key is not valid UTF-8.
+    InvalidUtf8,
+    /// An object has more than one member with the same key.
+    DuplicateKey,
+    /// A number doesn't fit in a finite `f64`.
+    NonFiniteNumber,
+    /// The writer returned an error.
+    WriteFailed,
+}
+
+impl From<fmt::Error> for CanonicalError {
+    fn from(_: fmt::Error) -> Self {
+        Self::WriteFailed
+    }
+}
+
+// Formatted number on the stack, the longest `f64` in scientific notation has 24 bytes
+struct Buffer {
+    bytes: [u8; 32],
+    len: usize,
+}
+
+impl Buffer {
+    fn new() -> Self {
+        Self {
+            bytes: [0; 32],
+            len: 0,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+}
+
+impl Write for Buffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.bytes
+            .get_mut(self.len..end)
+            .ok_or(fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+fn write_zeroes(count: i32, out: &mut impl Write) -> fmt::Result {
+    (0..count).try_for_each(|_| out.write_char('0'))
+}
+
+// ECMA-262 Number::toString. `{:e}` gives the shortest number of digits that round trips,
+// but on a tie it doesn't pick the even digits like ECMAScript does, so the digits themselves
+// come from exact formatting with that precision
+fn write_number(number: f64, out: &mut impl Write) -> Result<(), CanonicalError> {
+    if !number.is_finite() {
+        return Err(CanonicalError::NonFiniteNumber);
+    }
+    if number == 0.0 {
+        return Ok(out.write_char('0')?);
+    }
+    if number < 0.0 {
+        out.write_char('-')?;
+    }
+
+    let mut shortest = Buffer::new();
+    write!(shortest, "{:e}", number.abs())?;
+    let precision = shortest
+        .as_str()
+        .bytes()
+        .take_while(|byte| *byte != b'e')
+        .filter(u8::is_ascii_digit)
+        .count()
+        - 1;
+    let mut scientific = Buffer::new();
+    write!(scientific, "{:.*e}", precision, number.abs())?;
+    let (mantissa, exponent) = scientific
+        .as_str()
+        .split_once('e')
+        .ok_or(CanonicalError::WriteFailed)?;
+    let mut digits = Buffer::new();
+    mantissa
+        .split('.')
+        .try_for_each(|part| digits.write_str(part))?;
+    let digits = digits.as_str();
+
+    // the value is 0.`digits` * 10^`n`
+    let k = digits.len() as i32;
+    let n = exponent
+        .parse::<i32>()
+        .map_err(|_| CanonicalError::WriteFailed)?
+        + 1;
+    if k <= n && n <= 21 {
+        out.write_str(digits)?;
+        write_zeroes(n - k, out)?;
+    } else if 0 < n && n <= 21 {
+        let (integral, fraction) = digits.split_at(n as usize);
+        write!(out, "{}.{}", integral, fraction)?;
+    } else if -6 < n && n <= 0 {
+        out.write_str("0.")?;
+        write_zeroes(-n, out)?;
+        out.write_str(digits)?;
+    } else {
+        let (first, rest) = digits.split_at(1);
+        out.write_str(first)?;
+        if !rest.is_empty() {
+            write!(out, ".{}", rest)?;
+        }
+        let sign = if n > 0 { '+' } else { '-' };
+        write!(out, "e{}{}", sign, (n - 1).abs())?;
+    }
+    Ok(())
+}
+
+fn write_text(bytes: &[u8], out: &mut impl Write) -> Result<(), CanonicalError> {
+    core::str::from_utf8(bytes).map_err(|_| CanonicalError::InvalidUtf8)?;
+    Ok(write_string(bytes, out)?)
+}
+
+// Keys are known to be UTF-8 by the time they are compared. Bytes of UTF-8 sort like code points,
+// which is the order of UTF-16 except that U+E000..=U+FFFF (lead bytes 0xEE and 0xEF)
+// come after the surrogate pairs of the characters above them (lead bytes 0xF0 to 0xF4).
+// The first differing bytes of two keys are in the same position of a character,
+// so only they decide.
+fn utf16_cmp(lhs: &[u8], rhs: &[u8]) -> Ordering {
+    let order = |byte: u8| match byte {
+        0xEE | 0xEF => byte + 0x10,
+        _ => byte,
+    };
+    match lhs.iter().zip(rhs).find(|(lhs, rhs)| lhs != rhs) {
+        Some((lhs, rhs)) => order(*lhs).cmp(&order(*rhs)),
+        None => lhs.len().cmp(&rhs.len()),
+    }
+}
+
+// Slots are positions of keys, only the key is decoded and not the value after it
+fn key_at<'a>(object: &Object<'a>, pos: u32) -> &'a [u8] {
+    String::decode_tlv(object.data, &mut (pos as usize)).unwrap_or_default()
+}
+
+fn write_object(
+    object: &Object,
+    scratch: &mut [u32],
+    out: &mut impl Write,
+) -> Result<(), CanonicalError> {
+    if scratch.len() < object.len() {
+        return Err(CanonicalError::ScratchTooSmall);
+    }
+    let (slots, scratch) = scratch.split_at_mut(object.len());
+
+    let mut members = object.iter();
+    for slot in slots.iter_mut() {
+        let mut pos = members.position();
+        skip_zeroes(object.data, &mut pos);
+        *slot = u32::try_from(pos).map_err(|_| CanonicalError::ScratchTooSmall)?;
+        let (key, _) = members.next().ok_or(CanonicalError::ScratchTooSmall)?;
+        core::str::from_utf8(key).map_err(|_| CanonicalError::InvalidUtf8)?;
+    }
+    slots.sort_unstable_by(|lhs, rhs| utf16_cmp(key_at(object, *lhs), key_at(object, *rhs)));
+    if slots
+        .windows(2)
+        .any(|pair| key_at(object, pair[0]) == key_at(object, pair[1]))
+    {
+        return Err(CanonicalError::DuplicateKey);
+    }
+
+    out.write_char('{')?;
+    for (idx, slot) in slots.iter().enumerate() {
+        if idx > 0 {
+            out.write_char(',')?;
+        }
+        if let Some((key, value)) = object.iter_from(*slot as usize).next() {
+            write_string(key, out)?;
+            out.write_char(':')?;
+            write_canonical(&value, scratch, out)?;
+        }
+    }
+    Ok(out.write_char('}')?)
+}
+
+fn write_array(
+    array: &Array,
+    scratch: &mut [u32],
+    out: &mut impl Write,
+) -> Result<(), CanonicalError> {
+    out.write_char('[')?;
+    for (idx, value) in array.iter().enumerate() {
+        if idx > 0 {
+            out.write_char(',')?;
+        }
+        write_canonical(&value, scratch, out)?;
+    }
+    Ok(out.write_char(']')?)
+}
+
+fn write_canonical(
+    value: &Value,
+    scratch: &mut [u32],
+    out: &mut impl Write,
+) -> Result<(), CanonicalError> {
+    match value {
+        Value::Object(object) => write_object(object, scratch, out),
+        Value::Array(array) => write_array(array, scratch, out),
+        Value::String(string) => write_text(string, out),
+        // numbers are IEEE 754 doubles in JCS, integers beyond 2^53 get rounded
+        Value::Integer(int) => write_number(*int as f64, out),
+        Value::Float(float) => write_number(*float, out),
+        Value::True => Ok(out.write_str("true")?),
+        Value::False => Ok(out.write_str("false")?),
+        Value::Null => Ok(out.write_str("null")?),
+    }
+}
+
+fn scratch_len(value: &Value) -> usize {
+    match value {
+        Value::Object(object) => {
+            object.len()
+                + object
+                    .iter()
+                    .map(|(_, value)| scratch_len(&value))
+                    .max()
+                    .unwrap_or(0)
+        }
+        Value::Array(array) => array
+            .iter()
+            .map(|value| scratch_len(&value))
+            .max()
+            .unwrap_or(0),
+        _ => 0,
+    }
+}
+
+impl Value<'_> {
+    /// Number of scratch slots that `Value::write_canonical` needs.
+    pub fn canonical_scratch_len(&self) -> usize {
+        scratch_len(self)
+    }
+
+    /// Writes the value in the RFC 8785 canonical form, members of objects are sorted in `scratch`.
+    pub fn write_canonical(
+        &self,
+        scratch: &mut [u32],
+        out: &mut impl Write,
+    ) -> Result<(), CanonicalError> {
+        write_canonical(self, scratch, out)
+    }
+
+    /// `Value::write_canonical` with scratch allocated for it.
+    #[cfg(feature = "std")]
+    pub fn to_canonical_string(&self) -> Result<std::string::String, CanonicalError> {
+        let mut scratch = std::vec![0; self.canonical_scratch_len()];
+        let mut out = std::string::String::new();
+        write_canonical(self, &mut scratch, &mut out)?;
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+extern crate std;
+
+#[cfg(test)]
+fn canonical(json: &str) -> Result<std::string::String, CanonicalError> {
+    let mut data = std::vec::Vec::from(json.as_bytes());
+    let value = crate::parser::Parser::parse(&mut data).unwrap();
+    let mut scratch = std::vec![0; value.canonical_scratch_len()];
+    let mut out = std::string::String::new();
+    value.write_canonical(&mut scratch, &mut out)?;
+    Ok(out)
+}
+
+#[test]
+fn test_canonical_rfc_examples() {
+    // RFC 8785, section 3.2.2
+    assert_eq!(
+        canonical(
+            r#"{
+                "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]
+            }"#
+        )
+        .as_deref(),
+        Ok(concat!(
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"#,
+            "\"string\":\"\u{20ac}$\\u000f\\nA'B\\\"\\\\\\\\\\\"/\"}"
+        ))
+    );
+
+    // RFC 8785, section 3.2.3, U+1F600 is a surrogate pair that sorts before U+FB33
+    assert_eq!(
+        canonical(
+            r#"{
+                "\u20ac": "Euro Sign",
+                "\r": "Carriage Return",
+                "\ufb33": "Hebrew Letter Dalet With Dagesh",
+                "1": "One",
+                "\ud83d\ude00": "Emoji: Grinning Face",
+                "\u0080": "Control",
+                "\u00f6": "Latin Small Letter O With Diaeresis"
+            }"#
+        )
+        .as_deref(),
+        Ok(concat!(
+            "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",",
+            "\"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",",
+            "\"\u{1f600}\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+        ))
+    );
+}
+
+#[test]
+fn test_canonical_numbers() {
+    // RFC 8785, appendix B
+    let cases = [
+        (0x0000000000000000, "0"),
+        (0x8000000000000000, "0"),
+        (0x0000000000000001, "5e-324"),
+        (0x8000000000000001, "-5e-324"),
+        (0x7fefffffffffffff, "1.7976931348623157e+308"),
+        (0xffefffffffffffff, "-1.7976931348623157e+308"),
+        (0x4340000000000000, "9007199254740992"),
+        (0xc340000000000000, "-9007199254740992"),
+        (0x4430000000000000, "295147905179352830000"),
+        (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+        (0x44b52d02c7e14af6, "1e+23"),
+        (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+        (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+        (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+        (0x444b1ae4d6e2ef50, "1e+21"),
+        (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+        (0x3eb0c6f7a0b5ed8d, "0.000001"),
+        (0x41b3de4355555553, "333333333.3333332"),
+        (0x41b3de4355555554, "333333333.33333325"),
+        (0x41b3de4355555555, "333333333.3333333"),
+        (0x41b3de4355555556, "333333333.3333334"),
+        (0x41b3de4355555557, "333333333.33333343"),
+        (0xbecbf647612f3696, "-0.0000033333333333333333"),
+        (0x43143ff3c1cb0959, "1424953923781206.2"),
+    ];
+    for (bits, expected) in cases {
+        let mut out = std::string::String::new();
+        write_number(f64::from_bits(bits), &mut out).unwrap();
+        assert_eq!(out, expected, "{:#x}", bits);
+    }
+    for bits in [0x7fffffffffffffff, 0x7ff0000000000000] {
+        let mut out = std::string::String::new();
+        assert_eq!(
+            write_number(f64::from_bits(bits), &mut out),
+            Err(CanonicalError::NonFiniteNumber)
+        );
+    }
+
+    assert_eq!(
+        canonical("[1, -0, 100, 1.5e2, 12345678901234567890, 0.1e-6]").as_deref(),
+        Ok("[1,0,100,150,12345678901234567000,1e-7]")
+    );
+}
+
+#[test]
+fn test_canonical_errors() {
+    assert_eq!(
+        canonical(r#"{"a": 1, "b": {"c": 2}, "a": 3}"#),
+        Err(CanonicalError::DuplicateKey)
+    );
+    assert_eq!(canonical("[1e400]"), Err(CanonicalError::NonFiniteNumber));
+
+    let mut data = *br#"{"b": [{"x": 1, "y": 2}], "a": {"z": {}}}"#;
+    let value = crate::parser::Parser::parse(&mut data).unwrap();
+    assert_eq!(value.canonical_scratch_len(), 4);
+    let mut out = std::string::String::new();
+    assert_eq!(
+        value.write_canonical(&mut [0; 3], &mut out),
+        Err(CanonicalError::ScratchTooSmall)
+    );
+    let mut out = std::string::String::new();
+    value.write_canonical(&mut [0; 4], &mut out).unwrap();
+    assert_eq!(out, r#"{"a":{"z":{}},"b":[{"x":1,"y":2}]}"#);
+
+    let mut out = std::string::String::new();
+    assert_eq!(
+        Value::String(b"\xff").write_canonical(&mut [], &mut out),
+        Err(CanonicalError::InvalidUtf8)
+    );
+}
+
+#[test]
+fn test_utf16_cmp() {
+    let keys = [
+        "",
+        "a",
+        "ab",
+        "\u{7f}",
+        "\u{80}",
+        "\u{7ff}",
+        "\u{800}",
+        "\u{d7ff}",
+        "\u{e000}",
+        "\u{ffff}",
+        "\u{10000}",
+        "\u{1f600}",
+        "\u{10ffff}",
+        "a\u{ffff}",
+        "a\u{10000}",
+    ];
+    for lhs in keys {
+        for rhs in keys {
+            assert_eq!(
+                utf16_cmp(lhs.as_bytes(), rhs.as_bytes()),
+                lhs.encode_utf16().cmp(rhs.encode_utf16()),
+                "{:?} {:?}",
+                lhs,
+                rhs
+            );
+        }
+    }
+}
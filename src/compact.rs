@@ -0,0 +1,168 @@
+use crate::{
+    length::Length,
+    mask::{ARRAY_MASK, NUMBER_MASK, OBJECT_MASK, STRING_MASK, TYPE_MASK},
+    number::Number,
+    parser::Parser,
+    skip_zeroes::skip_zeroes,
+};
+
+impl Parser {
+    /// Removes the zero padding from the bitmixed TLV in `data` by moving every
+    /// encoded value to the front, returns the dense prefix that holds the root value.
+    /// `data` has to be the output of a successful `Parser::parse`, for anything else
+    /// the prefix is empty and the content of `data` is unspecified.
+    pub fn compact(data: &mut [u8]) -> &[u8] {
+        let length = compacted_length(data).unwrap_or(0);
+        &data[..length]
+    }
+}
+
+// Moves the values to the front, returns the length of the dense prefix
+fn compacted_length(data: &mut [u8]) -> Option<usize> {
+    let mut read = 0;
+    let mut write = 0;
+    // number of values that are still expected, object keys included
+    let mut pending = 1_usize;
+
+    while pending > 0 {
+        skip_zeroes(data, &mut read);
+        if read >= data.len() {
+            return None;
+        }
+
+        let size = match data[read] & TYPE_MASK {
+            NUMBER_MASK => Number::length(data, read)?,
+            STRING_MASK if read + 1 < data.len() => 2 + Length::read(data, read).0,
+            ARRAY_MASK if read + 1 < data.len() => {
+                pending += Length::read(data, read).0;
+                2
+            }
+            OBJECT_MASK if read + 1 < data.len() => {
+                pending += 2 * Length::read(data, read).0;
+                2
+            }
+            _ => 1,
+        };
+        if read + size > data.len() {
+            return None;
+        }
+
+        data.copy_within(read..read + size, write);
+        read += size;
+        write += size;
+        pending -= 1;
+    }
+
+    Some(write)
+}
+
+#[test]
+fn test_compact() {
+    use crate::value::Value;
+
+    let mut data = *br#" {
+        "a": [1, 23, "x\ty"],
+        "b": {"c": null, "d": true, "e": false},
+        "f": "",
+        "g": []
+    } trailing"#;
+    Parser::parse(&mut data).unwrap();
+
+    let compacted = Parser::compact(&mut data);
+    assert_eq!(
+        compacted,
+        [
+            OBJECT_MASK | 4,
+            0,
+            STRING_MASK | 1,
+            0,
+            b'a',
+            ARRAY_MASK | 3,
+            0,
+            NUMBER_MASK | 1,
+            0b001_10010, // multibyte 2
+            0b0010_0011, // length = 2, 3
+            STRING_MASK | 3,
+            0,
+            b'x',
+            b'\t',
+            b'y',
+            STRING_MASK | 1,
+            0,
+            b'b',
+            OBJECT_MASK | 3,
+            0,
+            STRING_MASK | 1,
+            0,
+            b'c',
+            crate::mask::NULL_MASK,
+            STRING_MASK | 1,
+            0,
+            b'd',
+            crate::mask::TRUE_MASK,
+            STRING_MASK | 1,
+            0,
+            b'e',
+            crate::mask::FALSE_MASK,
+            STRING_MASK | 1,
+            0,
+            b'f',
+            STRING_MASK,
+            0,
+            STRING_MASK | 1,
+            0,
+            b'g',
+            ARRAY_MASK,
+            0,
+        ]
+    );
+
+    let Value::Object(object) = Value::try_from_tlv(compacted).unwrap() else {
+        panic!("expected an object");
+    };
+    let mut iter = object.iter();
+    let (key, Value::Array(array)) = iter.next().unwrap() else {
+        panic!("expected an array");
+    };
+    assert_eq!(key, b"a");
+    let mut elements = array.iter();
+    assert!(matches!(elements.next(), Some(Value::Integer(1))));
+    assert!(matches!(elements.next(), Some(Value::Integer(23))));
+    assert!(matches!(elements.next(), Some(Value::String(b"x\ty"))));
+    assert!(elements.next().is_none());
+    assert!(iter
+        .map(|(key, _)| key)
+        .eq([b"b", b"f", b"g"].map(|key| &key[..])));
+}
+
+#[test]
+fn test_compact_scalar() {
+    let mut data = *b"  true  ";
+    Parser::parse(&mut data).unwrap();
+    assert_eq!(Parser::compact(&mut data), [crate::mask::TRUE_MASK]);
+
+    let mut data = *b"";
+    assert!(Parser::compact(&mut data).is_empty());
+}
+
+#[test]
+fn test_compact_long_number() {
+    use crate::value::Value;
+
+    // 1e-300 written out in full
+    let mut data = [b'0'; 311];
+    data[..4].copy_from_slice(b"[ 0.");
+    data[303] = b'1';
+    data[304..].copy_from_slice(b" , -2 ]");
+    Parser::parse(&mut data).unwrap();
+
+    let compacted = Parser::compact(&mut data);
+    assert_eq!(compacted.len(), 2 + 302 + 2);
+    let Value::Array(array) = Value::try_from_tlv(compacted).unwrap() else {
+        panic!("expected an array");
+    };
+    let mut elements = array.iter();
+    assert!(matches!(elements.next(), Some(Value::Float(float)) if float == 1e-300));
+    assert!(matches!(elements.next(), Some(Value::Integer(-2))));
+    assert!(elements.next().is_none());
+}
@@ -0,0 +1,448 @@
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use crate::{array::Array, length::MAX_LENGTH, object::Object, value::Value};
+
+//
+// Values are ordered by type first:
+//   null < false < true < numbers < strings < arrays < objects
+//
+// then by content:
+//   1. numbers by value, integers and floats are compared exactly (1 == 1.0),
+//      -0.0 equals 0.0 and NaN (which JSON can't express) is above every other number
+//   2. strings byte by byte
+//   3. arrays element by element, a prefix goes first
+//   4. objects member by member (by key, then by value) in the order they are stored,
+//      so objects with the same members in a different order are not equal,
+//      see `Value::eq_ignoring_key_order` for that
+//
+// `Hash` hashes numbers that are equal the same way.
+//
+
+fn rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::False => 1,
+        Value::True => 2,
+        Value::Integer(_) | Value::Float(_) => 3,
+        Value::String(_) => 4,
+        Value::Array(_) => 5,
+        Value::Object(_) => 6,
+    }
+}
+
+fn cmp_floats(lhs: f64, rhs: f64) -> Ordering {
+    match (lhs.is_nan(), rhs.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => lhs.partial_cmp(&rhs).unwrap_or(Ordering::Equal),
+    }
+}
+
+// 2^63, the first float above i64::MAX
+const I64_END: f64 = 9_223_372_036_854_775_808.0;
+
+fn cmp_integer_float(lhs: i64, rhs: f64) -> Ordering {
+    if rhs.is_nan() {
+        return Ordering::Less;
+    }
+    if rhs < -I64_END {
+        return Ordering::Greater;
+    }
+    if rhs >= I64_END {
+        return Ordering::Less;
+    }
+    // casting truncates towards zero and is exact in this range
+    let trunc = rhs as i64;
+    lhs.cmp(&trunc).then_with(|| cmp_floats(trunc as f64, rhs))
+}
+
+fn cmp_numbers(lhs: &Value, rhs: &Value) -> Ordering {
+    match (lhs, rhs) {
+        (Value::Integer(lhs), Value::Integer(rhs)) => lhs.cmp(rhs),
+        (Value::Integer(lhs), Value::Float(rhs)) => cmp_integer_float(*lhs, *rhs),
+        (Value::Float(lhs), Value::Integer(rhs)) => cmp_integer_float(*rhs, *lhs).reverse(),
+        (Value::Float(lhs), Value::Float(rhs)) => cmp_floats(*lhs, *rhs),
+        _ => unreachable!("not numbers"),
+    }
+}
+
+impl Ord for Value<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        rank(self)
+            .cmp(&rank(other))
+            .then_with(|| match (self, other) {
+                (Value::String(lhs), Value::String(rhs)) => lhs.cmp(rhs),
+                (Value::Array(lhs), Value::Array(rhs)) => lhs.cmp(rhs),
+                (Value::Object(lhs), Value::Object(rhs)) => lhs.cmp(rhs),
+                (Value::Integer(_) | Value::Float(_), _) => cmp_numbers(self, other),
+                _ => Ordering::Equal,
+            })
+    }
+}
+
+impl PartialOrd for Value<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Value<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value<'_> {}
+
+impl Hash for Value<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u8(rank(self));
+        match self {
+            Value::Integer(int) => int.hash(state),
+            Value::Float(float) => {
+                if (-I64_END..I64_END).contains(float) && (*float as i64) as f64 == *float {
+                    (*float as i64).hash(state)
+                } else if float.is_nan() {
+                    u64::MAX.hash(state)
+                } else {
+                    float.to_bits().hash(state)
+                }
+            }
+            Value::String(string) => string.hash(state),
+            Value::Array(array) => array.hash(state),
+            Value::Object(object) => object.hash(state),
+            Value::True | Value::False | Value::Null => {}
+        }
+    }
+}
+
+impl Ord for Array<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl PartialOrd for Array<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Array<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Array<'_> {}
+
+impl Hash for Array<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        self.iter().for_each(|value| value.hash(state));
+    }
+}
+
+impl Ord for Object<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl PartialOrd for Object<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Object<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Object<'_> {}
+
+impl Hash for Object<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        self.iter().for_each(|member| member.hash(state));
+    }
+}
+
+impl Value<'_> {
+    /// Equality where objects with the same members in any order are equal, at every level.
+    /// Members are matched by key with a scan of the other object, which is quadratic
+    /// in the number of members. Each value is compared only with the values that have
+    /// the same key on the other side, so nesting doesn't add to the cost.
+    pub fn eq_ignoring_key_order(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Array(lhs), Value::Array(rhs)) => {
+                lhs.len() == rhs.len()
+                    && lhs
+                        .iter()
+                        .zip(rhs.iter())
+                        .all(|(lhs, rhs)| lhs.eq_ignoring_key_order(&rhs))
+            }
+            (Value::Object(lhs), Value::Object(rhs)) => lhs.eq_ignoring_key_order(rhs),
+            _ => self == other,
+        }
+    }
+}
+
+impl Object<'_> {
+    /// See `Value::eq_ignoring_key_order`.
+    pub fn eq_ignoring_key_order(&self, other: &Object) -> bool {
+        let count = |object: &Object, key: &[u8]| {
+            object
+                .iter()
+                .filter(|(other_key, _)| *other_key == key)
+                .count()
+        };
+        self.len() == other.len()
+            && self.iter().enumerate().all(|(idx, (key, value))| {
+                // every key is checked once, where it first appears
+                if self.iter().take(idx).any(|(other_key, _)| other_key == key) {
+                    return true;
+                }
+                match (count(self, key), count(other, key)) {
+                    (1, 1) => other
+                        .get(key)
+                        .is_some_and(|other| value.eq_ignoring_key_order(&other)),
+                    (lhs, rhs) => lhs == rhs && same_values(self, other, key),
+                }
+            })
+    }
+}
+
+// Whether a key that appears more than once has the same values on both sides, in any order:
+// every value on the left takes the first equal value on the right that isn't taken yet
+fn same_values(lhs: &Object, rhs: &Object, key: &[u8]) -> bool {
+    let mut taken = [0_u64; MAX_LENGTH / 64];
+    lhs.iter()
+        .filter(|(other_key, _)| *other_key == key)
+        .all(|(_, value)| {
+            let found = rhs
+                .iter()
+                .filter(|(other_key, _)| *other_key == key)
+                .enumerate()
+                .find(|(idx, (_, other))| {
+                    taken[idx / 64] & (1 << (idx % 64)) == 0 && value.eq_ignoring_key_order(other)
+                });
+            match found {
+                Some((idx, _)) => {
+                    taken[idx / 64] |= 1 << (idx % 64);
+                    true
+                }
+                None => false,
+            }
+        })
+}
+
+// Comparisons with Rust values
+
+macro_rules! eq_rust {
+    ($ty:ty, |$value:ident, $other:ident| $eq:expr) => {
+        impl PartialEq<$ty> for Value<'_> {
+            fn eq(&self, $other: &$ty) -> bool {
+                let $value = self;
+                $eq
+            }
+        }
+    };
+}
+
+macro_rules! eq_integers {
+    ($($ty:ty)*) => {
+        $(eq_rust!($ty, |value, other| {
+            i64::try_from(*other).is_ok_and(|other| *value == Value::Integer(other))
+        });)*
+    };
+}
+
+eq_integers!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize);
+eq_rust!(f32, |value, other| *value == Value::Float(*other as f64));
+eq_rust!(f64, |value, other| *value == Value::Float(*other));
+eq_rust!(bool, |value, other| *value
+    == if *other { Value::True } else { Value::False });
+eq_rust!(str, |value, other| *value
+    == Value::String(other.as_bytes()));
+eq_rust!(&str, |value, other| *value
+    == Value::String(other.as_bytes()));
+
+// Debug output that shows the contents of containers instead of TLV bytes
+
+struct Key<'a>(&'a [u8]);
+
+impl fmt::Debug for Key<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match core::str::from_utf8(self.0) {
+            Ok(key) => key.fmt(f),
+            Err(_) => self.0.fmt(f),
+        }
+    }
+}
+
+impl fmt::Debug for Array<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl fmt::Debug for Object<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.iter().map(|(key, value)| (Key(key), value)))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+fn parse(data: &mut [u8]) -> Value<'_> {
+    crate::parser::Parser::parse(data).unwrap()
+}
+
+#[test]
+fn test_eq() {
+    let mut lhs = *br#"{"a": [1, 2.0, "xA"], "b": {"c": null}}"#;
+    let mut rhs = *br#"{ "a" : [1.0, 2, "xA"], "b" : {"c" : null} }"#;
+    assert_eq!(parse(&mut lhs), parse(&mut rhs));
+
+    let mut lhs = *br#"{"a": 1, "b": [{"c": 1, "d": 2}]}"#;
+    let mut rhs = *br#"{"b": [{"d": 2, "c": 1}], "a": 1}"#;
+    let (lhs, rhs) = (parse(&mut lhs), parse(&mut rhs));
+    assert_ne!(lhs, rhs);
+    assert!(lhs.eq_ignoring_key_order(&rhs));
+
+    let mut lhs = *br#"{"a": 1, "a": 1, "b": 2}"#;
+    let mut rhs = *br#"{"a": 1, "b": 2, "b": 2}"#;
+    assert!(!parse(&mut lhs).eq_ignoring_key_order(&parse(&mut rhs)));
+
+    let mut lhs = *br#"{"a": 1, "b": [], "a": {"c": 2, "d": 3}, "a": 1}"#;
+    let mut rhs = *br#"{"a": {"d": 3, "c": 2}, "a": 1, "b": [], "a": 1}"#;
+    assert!(parse(&mut lhs).eq_ignoring_key_order(&parse(&mut rhs)));
+    let mut lhs = *br#"{"a": 1, "a": 1, "a": 2}"#;
+    let mut rhs = *br#"{"a": 2, "a": 1, "a": 2}"#;
+    assert!(!parse(&mut lhs).eq_ignoring_key_order(&parse(&mut rhs)));
+
+    let mut data = *br#"[[1], [1, 2]]"#;
+    let Value::Array(array) = parse(&mut data) else {
+        unreachable!()
+    };
+    let mut elements = array.iter();
+    assert_ne!(elements.next(), elements.next());
+}
+
+#[test]
+fn test_numbers() {
+    assert_eq!(Value::Integer(1), Value::Float(1.0));
+    assert_eq!(Value::Integer(0), Value::Float(-0.0));
+    assert_eq!(Value::Float(0.0), Value::Float(-0.0));
+    assert_eq!(Value::Float(f64::NAN), Value::Float(f64::NAN));
+    assert!(Value::Integer(i64::MAX) < Value::Float(I64_END));
+    assert!(Value::Integer(i64::MIN) == Value::Float(-I64_END));
+    assert!(Value::Integer(i64::MAX) > Value::Float(9.2e18));
+    assert!(Value::Integer(2) > Value::Float(1.5));
+    assert!(Value::Integer(-2) < Value::Float(-1.5));
+    assert!(Value::Integer(1) < Value::Float(1.5));
+    assert!(Value::Float(f64::INFINITY) < Value::Float(f64::NAN));
+    assert!(Value::Integer(i64::MAX) < Value::Float(f64::NAN));
+}
+
+#[test]
+fn test_order() {
+    let mut data = *br#"[null, false, true, -1, 2.5, 3, "", "a", "b", [], [1], [1, 2], [2], {}, {"a": 2}, {"b": 1}]"#;
+    let Value::Array(array) = parse(&mut data) else {
+        unreachable!()
+    };
+    let mut elements = array.iter().peekable();
+    while let Some(value) = elements.next() {
+        if let Some(next) = elements.peek() {
+            assert!(value < *next, "{:?} < {:?}", value, next);
+            assert!(*next > value);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_hash() {
+    extern crate std;
+    use std::{collections::hash_map::DefaultHasher, hash::Hasher};
+
+    fn hash(value: &Value) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let mut lhs = *br#"{"a": [1, -0.0, 2.5e0, "x"]}"#;
+    let mut rhs = *br#"{"a": [1.0, 0, 25e-1, "x"]}"#;
+    assert_eq!(hash(&parse(&mut lhs)), hash(&parse(&mut rhs)));
+    assert_ne!(hash(&Value::Integer(1)), hash(&Value::True));
+
+    let mut data = *br#"[[], {}]"#;
+    let Value::Array(array) = parse(&mut data) else {
+        unreachable!()
+    };
+    let mut elements = array.iter();
+    assert_ne!(
+        hash(&elements.next().unwrap()),
+        hash(&elements.next().unwrap())
+    );
+}
+
+#[test]
+fn test_literals() {
+    let mut data = *br#"["x", 5, 5.5, true, null, -1]"#;
+    let Value::Array(array) = parse(&mut data) else {
+        unreachable!()
+    };
+    let mut elements = array.iter();
+    let x = elements.next().unwrap();
+    assert!(x == "x" && x == *"x" && x != "y" && x != 1);
+    let five = elements.next().unwrap();
+    assert!(five == 5 && five == 5_u8 && five == 5.0 && five != "5");
+    assert!(elements.next().unwrap() == 5.5_f32);
+    assert!(elements.next().unwrap() == true);
+    assert!(elements.next().unwrap() != false);
+    assert!(elements.next().unwrap() != 0_u64);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_debug() {
+    extern crate std;
+    use std::format;
+
+    let mut data = *br#"{"a": [1, true], "b": {}}"#;
+    assert_eq!(
+        format!("{:?}", parse(&mut data)),
+        r#"Object({"a": Array([Integer(1), True]), "b": Object({})})"#
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_eq_ignoring_key_order_deep() {
+    extern crate std;
+    use std::vec::Vec;
+
+    // every level used to be compared twice as often as the one above it
+    let nested = |leaf: &str| {
+        let mut data = Vec::new();
+        data.extend(br#"{"a": "#.repeat(200));
+        data.extend(leaf.as_bytes());
+        data.extend(b"}".repeat(200));
+        data
+    };
+    let (mut lhs, mut rhs) = (nested("1"), nested("2"));
+    let (lhs, rhs) = (parse(&mut lhs), parse(&mut rhs));
+    assert!(lhs.eq_ignoring_key_order(&lhs));
+    assert!(!lhs.eq_ignoring_key_order(&rhs));
+}
@@ -0,0 +1,263 @@
+use crate::{
+    length::Length,
+    mask::{ARRAY_MASK, OBJECT_MASK, TYPE_MASK},
+    skip_zeroes::skip_zeroes,
+    stack::Stack,
+    string::String,
+    tlv::DecodeTLV,
+    value::Value,
+};
+
+// Positions are stored as `u32` to keep cursors small
+#[derive(Debug, Clone, Copy, Default)]
+struct Node {
+    // where the value starts, or its key if it's a member of an object
+    pos: u32,
+    // number of siblings after it, taken from the header of the parent
+    remaining: u16,
+    key: bool,
+}
+
+// End of the value at `pos`, containers are skipped by counting what's left in them
+// instead of recursing
+fn value_end(data: &[u8], mut pos: usize) -> Option<usize> {
+    let mut pending = 1_usize;
+    while pending > 0 {
+        skip_zeroes(data, &mut pos);
+        match *data.get(pos)? & TYPE_MASK {
+            mask @ (ARRAY_MASK | OBJECT_MASK) if pos + 1 < data.len() => {
+                let Length(length) = Length::read(data, pos);
+                pending += if mask == OBJECT_MASK {
+                    2 * length
+                } else {
+                    length
+                };
+                pos += 2;
+            }
+            _ => {
+                if !Value::skip_tlv(data, &mut pos) {
+                    return None;
+                }
+            }
+        }
+        pending -= 1;
+    }
+    Some(pos)
+}
+
+/// Position of a value in a TLV buffer (i.e. `data` after `Parser::parse`)
+/// that can move to its children, siblings and parent without recursion.
+///
+/// Every move returns a new cursor, the old one stays where it was.
+/// Moves only read container headers, a walk over the whole document skips every value once.
+#[derive(Debug, Clone, Copy)]
+pub struct Cursor<'a> {
+    data: &'a [u8],
+    node: Node,
+    // end of the value once it's known, 0 until then
+    end: u32,
+    parents: Stack<Node, { Cursor::MAX_DEPTH }>,
+}
+
+impl<'a> Cursor<'a> {
+    /// Maximum depth a cursor can descend to, `first_child` returns `None` below it.
+    pub const MAX_DEPTH: usize = 32;
+
+    /// Cursor at the root value, `None` if there's no value
+    /// or the buffer is longer than `u32::MAX` bytes.
+    pub fn new(data: &'a [u8]) -> Option<Self> {
+        if u32::try_from(data.len()).is_err() {
+            return None;
+        }
+        let mut pos = 0;
+        skip_zeroes(data, &mut pos);
+        let end = value_end(data, pos)?;
+        Some(Self {
+            data,
+            node: Node {
+                pos: pos as u32,
+                remaining: 0,
+                key: false,
+            },
+            end: end as u32,
+            parents: Stack::new(),
+        })
+    }
+
+    pub fn value(&self) -> Option<Value<'a>> {
+        Value::decode_tlv(self.data, &mut self.offset())
+    }
+
+    /// Key of the value if it's a member of an object.
+    pub fn key(&self) -> Option<&'a [u8]> {
+        if !self.node.key {
+            return None;
+        }
+        String::decode_tlv(self.data, &mut (self.node.pos as usize))
+    }
+
+    /// Number of containers above the value.
+    pub fn depth(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// Position of the value in the TLV buffer.
+    pub fn offset(&self) -> usize {
+        let mut pos = self.node.pos as usize;
+        if self.node.key && String::skip_tlv(self.data, &mut pos) {
+            skip_zeroes(self.data, &mut pos);
+        }
+        pos
+    }
+
+    fn end(&self) -> Option<usize> {
+        match self.end {
+            0 => value_end(self.data, self.offset()),
+            end => Some(end as usize),
+        }
+    }
+
+    // Cursor at the node that starts at or after `pos`
+    fn moved(&self, mut pos: usize, remaining: u16, key: bool) -> Option<Self> {
+        skip_zeroes(self.data, &mut pos);
+        if pos >= self.data.len() {
+            return None;
+        }
+        let mut cursor = *self;
+        cursor.node = Node {
+            pos: pos as u32,
+            remaining,
+            key,
+        };
+        cursor.end = 0;
+        Some(cursor)
+    }
+
+    /// The first element of an array or the first member of an object.
+    pub fn first_child(&self) -> Option<Self> {
+        let pos = self.offset();
+        let mask = *self.data.get(pos)? & TYPE_MASK;
+        if !matches!(mask, ARRAY_MASK | OBJECT_MASK) || pos + 1 >= self.data.len() {
+            return None;
+        }
+        let Length(length) = Length::read(self.data, pos);
+        let remaining = u16::try_from(length.checked_sub(1)?).ok()?;
+
+        let mut cursor = self.moved(pos + 2, remaining, mask == OBJECT_MASK)?;
+        cursor.parents.push(self.node)?;
+        Some(cursor)
+    }
+
+    pub fn next_sibling(&self) -> Option<Self> {
+        if self.node.remaining == 0 {
+            return None;
+        }
+        self.moved(self.end()?, self.node.remaining - 1, self.node.key)
+    }
+
+    pub fn parent(&self) -> Option<Self> {
+        let mut cursor = *self;
+        cursor.node = cursor.parents.pop()?;
+        // the parent ends where its last child does
+        cursor.end = match self.node.remaining {
+            0 => self.end().map_or(0, |end| end as u32),
+            _ => 0,
+        };
+        Some(cursor)
+    }
+}
+
+#[test]
+fn test_cursor_walk() {
+    extern crate std;
+    use crate::parser::Parser;
+    use std::{format, string::String};
+
+    fn walk(data: &[u8]) -> String {
+        // prints every value in document order without recursion
+        let mut out = String::new();
+        let mut cursor = Cursor::new(data);
+        while let Some(current) = cursor {
+            if let Some(key) = current.key() {
+                out += &format!("{}:", core::str::from_utf8(key).unwrap());
+            }
+            out += &match current.value().unwrap() {
+                Value::Object(_) => format!("{{{}", current.depth()),
+                Value::Array(_) => format!("[{}", current.depth()),
+                Value::String(string) => format!("{:?}", core::str::from_utf8(string).unwrap()),
+                Value::Integer(int) => format!("{}", int),
+                value => format!("{:?}", value),
+            };
+            out += " ";
+
+            cursor = current.first_child().or_else(|| {
+                let mut up = current;
+                loop {
+                    if let Some(sibling) = up.next_sibling() {
+                        return Some(sibling);
+                    }
+                    up = up.parent()?;
+                    out += "^ ";
+                }
+            });
+        }
+        out
+    }
+
+    let mut data = *br#"{"a": [1, {"b": "x"}, []], "c": {}, "d": null}"#;
+    Parser::parse(&mut data).unwrap();
+    assert_eq!(walk(&data), r#"{0 a:[1 1 {2 b:"x" ^ [2 ^ c:{1 d:Null ^ "#);
+
+    let mut data = *b"  42";
+    Parser::parse(&mut data).unwrap();
+    assert_eq!(walk(&data), "42 ");
+}
+
+#[test]
+fn test_cursor_moves() {
+    use crate::parser::Parser;
+
+    let mut data = *br#"[{"k": [true]}, 2]"#;
+    Parser::parse(&mut data).unwrap();
+    let root = Cursor::new(&data).unwrap();
+    assert!(root.parent().is_none() && root.next_sibling().is_none());
+
+    let first = root.first_child().unwrap();
+    let k = first.first_child().unwrap();
+    assert_eq!(k.key(), Some(&b"k"[..]));
+    assert!(k.next_sibling().is_none());
+    let inner = k.first_child().unwrap();
+    assert_eq!(inner.depth(), 3);
+    assert!(matches!(inner.value(), Some(Value::True)));
+    assert!(inner.first_child().is_none());
+
+    // moves don't change the cursor they start from
+    let second = first.next_sibling().unwrap();
+    assert!(matches!(second.value(), Some(Value::Integer(2))));
+    assert_eq!(first.key(), None);
+    assert_eq!(
+        inner.parent().unwrap().parent().unwrap().offset(),
+        first.offset()
+    );
+    assert_eq!(second.parent().unwrap().offset(), root.offset());
+
+    assert!(Cursor::new(&[0, 0]).is_none());
+    // 8 bytes per level
+    assert!(core::mem::size_of::<Cursor>() <= 8 * Cursor::MAX_DEPTH + 48);
+}
+
+#[test]
+fn test_cursor_max_depth() {
+    use crate::parser::Parser;
+
+    let mut data = [b'['; 2 * Cursor::MAX_DEPTH + 4];
+    data[Cursor::MAX_DEPTH + 2..].fill(b']');
+    Parser::parse(&mut data).unwrap();
+    let mut cursor = Cursor::new(&data).unwrap();
+    while let Some(child) = cursor.first_child() {
+        cursor = child;
+    }
+    // the array at this depth isn't empty, but its child is out of reach
+    assert_eq!(cursor.depth(), Cursor::MAX_DEPTH);
+    assert!(matches!(cursor.value(), Some(Value::Array(array)) if array.len() == 1));
+}
@@ -0,0 +1,452 @@
+use core::fmt::{self, Write};
+
+use crate::{
+    array::Array,
+    object::Object,
+    serialize::{write_escaped, write_value},
+    value::Value,
+};
+
+/// Difference between two values at some `Path`, see `diff`.
+#[derive(Debug, Clone, Copy)]
+pub enum Change<'a> {
+    Added(Value<'a>),
+    Removed(Value<'a>),
+    /// A value changed but kept its type (numbers are one type, so are `true` and `false`).
+    Replaced {
+        from: Value<'a>,
+        to: Value<'a>,
+    },
+    TypeChanged {
+        from: Value<'a>,
+        to: Value<'a>,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Segment<'p> {
+    Root,
+    Key(&'p [u8]),
+    Index(usize),
+}
+
+/// Location of a `Change` that lives on the stack of `diff`,
+/// its `Display` output is a JSON Pointer.
+#[derive(Debug, Clone, Copy)]
+pub struct Path<'p> {
+    parent: Option<&'p Path<'p>>,
+    segment: Segment<'p>,
+}
+
+impl<'p> Path<'p> {
+    pub(crate) const ROOT: Path<'static> = Path {
+        parent: None,
+        segment: Segment::Root,
+    };
+
+    pub(crate) fn key(&'p self, key: &'p [u8]) -> Self {
+        Self {
+            parent: Some(self),
+            segment: Segment::Key(key),
+        }
+    }
+
+    pub(crate) fn index(&'p self, index: usize) -> Self {
+        Self {
+            parent: Some(self),
+            segment: Segment::Index(index),
+        }
+    }
+}
+
+impl fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(parent) = self.parent {
+            parent.fmt(f)?;
+        }
+        match self.segment {
+            Segment::Root => Ok(()),
+            Segment::Index(index) => write!(f, "/{}", index),
+            Segment::Key(key) => {
+                f.write_char('/')?;
+                for chunk in key.utf8_chunks() {
+                    for char in chunk.valid().chars() {
+                        match char {
+                            '~' => f.write_str("~0")?,
+                            '/' => f.write_str("~1")?,
+                            _ => f.write_char(char)?,
+                        }
+                    }
+                    if !chunk.invalid().is_empty() {
+                        f.write_char(char::REPLACEMENT_CHARACTER)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn kind(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::True | Value::False => 1,
+        Value::Integer(_) | Value::Float(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    }
+}
+
+// Members after the first one with a key are ignored on both sides
+fn is_duplicate(object: &Object, idx: usize, key: &[u8]) -> bool {
+    object.iter().take(idx).any(|(other, _)| other == key)
+}
+
+fn diff_objects<'a, F>(path: &Path, lhs: &Object<'a>, rhs: &Object<'a>, on_change: &mut F)
+where
+    F: FnMut(&Path, Change<'a>),
+{
+    for (idx, (key, from)) in lhs.iter().enumerate() {
+        if is_duplicate(lhs, idx, key) {
+            continue;
+        }
+        let path = path.key(key);
+        match rhs.get(key) {
+            Some(to) => diff_at(&path, from, to, on_change),
+            None => on_change(&path, Change::Removed(from)),
+        }
+    }
+    for (idx, (key, to)) in rhs.iter().enumerate() {
+        if !is_duplicate(rhs, idx, key) && lhs.get(key).is_none() {
+            on_change(&path.key(key), Change::Added(to));
+        }
+    }
+}
+
+fn diff_arrays<'a, F>(path: &Path, lhs: &Array<'a>, rhs: &Array<'a>, on_change: &mut F)
+where
+    F: FnMut(&Path, Change<'a>),
+{
+    let (mut from, mut to) = (lhs.iter(), rhs.iter());
+    let mut idx = 0;
+    loop {
+        match (from.next(), to.next()) {
+            (Some(lhs), Some(rhs)) => diff_at(&path.index(idx), lhs, rhs, on_change),
+            // elements past the end of `rhs` are all removed at the same index,
+            // so that the changes can be applied in order
+            (Some(lhs), None) => {
+                on_change(&path.index(idx), Change::Removed(lhs));
+                continue;
+            }
+            (None, Some(rhs)) => on_change(&path.index(idx), Change::Added(rhs)),
+            (None, None) => break,
+        }
+        idx += 1;
+    }
+}
+
+fn diff_at<'a, F>(path: &Path, lhs: Value<'a>, rhs: Value<'a>, on_change: &mut F)
+where
+    F: FnMut(&Path, Change<'a>),
+{
+    match (&lhs, &rhs) {
+        (Value::Object(from), Value::Object(to)) => diff_objects(path, from, to, on_change),
+        (Value::Array(from), Value::Array(to)) => diff_arrays(path, from, to, on_change),
+        _ if lhs == rhs => {}
+        _ if kind(&lhs) == kind(&rhs) => on_change(path, Change::Replaced { from: lhs, to: rhs }),
+        _ => on_change(path, Change::TypeChanged { from: lhs, to: rhs }),
+    }
+}
+
+/// Compares `lhs` with `rhs` and calls `on_change` for every difference.
+///
+/// Object members are matched by key (order doesn't matter, only the first member
+/// with a key counts), array elements by index. Numbers are compared by value.
+/// Elements removed from the end of an array are all reported at the first removed index,
+/// so the changes can be applied one after another.
+pub fn diff<'a>(lhs: &Value<'a>, rhs: &Value<'a>, mut on_change: impl FnMut(&Path, Change<'a>)) {
+    diff_at(&Path::ROOT, *lhs, *rhs, &mut on_change);
+}
+
+/// `diff` collected into a list of JSON Pointers and changes.
+#[cfg(feature = "std")]
+pub fn changes<'a>(
+    lhs: &Value<'a>,
+    rhs: &Value<'a>,
+) -> std::vec::Vec<(std::string::String, Change<'a>)> {
+    use std::string::ToString;
+
+    let mut changes = std::vec::Vec::new();
+    diff(lhs, rhs, |path, change| {
+        changes.push((path.to_string(), change))
+    });
+    changes
+}
+
+/// Renders the changes between two values, one per line:
+/// `+` for added values, `-` for removed ones and `~` for replaced ones.
+pub struct Diff<'v, 'a> {
+    lhs: &'v Value<'a>,
+    rhs: &'v Value<'a>,
+}
+
+impl<'v, 'a> Diff<'v, 'a> {
+    pub fn new(lhs: &'v Value<'a>, rhs: &'v Value<'a>) -> Self {
+        Self { lhs, rhs }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let mut empty = true;
+        diff(self.lhs, self.rhs, |_, _| empty = false);
+        empty
+    }
+}
+
+impl fmt::Display for Diff<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut result = Ok(());
+        diff(self.lhs, self.rhs, |path, change| {
+            if result.is_err() {
+                return;
+            }
+            result = match change {
+                Change::Added(to) => writeln!(f, "+ {}: {}", path, to),
+                Change::Removed(from) => writeln!(f, "- {}: {}", path, from),
+                Change::Replaced { from, to } => writeln!(f, "~ {}: {} -> {}", path, from, to),
+                Change::TypeChanged { from, to } => {
+                    writeln!(f, "~ {}: {} -> {} (type changed)", path, from, to)
+                }
+            };
+        });
+        result
+    }
+}
+
+/// Renders the changes between two values as an RFC 6902 JSON Patch
+/// that turns `lhs` into `rhs`.
+pub struct JsonPatch<'v, 'a> {
+    lhs: &'v Value<'a>,
+    rhs: &'v Value<'a>,
+}
+
+impl<'v, 'a> JsonPatch<'v, 'a> {
+    pub fn new(lhs: &'v Value<'a>, rhs: &'v Value<'a>) -> Self {
+        Self { lhs, rhs }
+    }
+}
+
+// Escapes everything written through it as the contents of a JSON string
+struct Escaped<'w, W>(&'w mut W);
+
+impl<W: Write> Write for Escaped<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        write_escaped(s.as_bytes(), self.0)
+    }
+}
+
+fn write_operation(
+    f: &mut impl Write,
+    op: &str,
+    path: &Path,
+    value: Option<&Value>,
+) -> fmt::Result {
+    write!(f, "{{\"op\":\"{}\",\"path\":\"", op)?;
+    write!(Escaped(f), "{}", path)?;
+    f.write_char('"')?;
+    if let Some(value) = value {
+        f.write_str(",\"value\":")?;
+        write_value(value, None, f)?;
+    }
+    f.write_char('}')
+}
+
+impl fmt::Display for JsonPatch<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut result = f.write_char('[');
+        let mut first = true;
+        diff(self.lhs, self.rhs, |path, change| {
+            if result.is_err() {
+                return;
+            }
+            if !first {
+                result = f.write_char(',');
+            }
+            first = false;
+            result = result.and_then(|()| match change {
+                Change::Added(to) => write_operation(f, "add", path, Some(&to)),
+                Change::Removed(_) => write_operation(f, "remove", path, None),
+                Change::Replaced { to, .. } | Change::TypeChanged { to, .. } => {
+                    write_operation(f, "replace", path, Some(&to))
+                }
+            });
+        });
+        result.and_then(|()| f.write_char(']'))
+    }
+}
+
+/// Like `assert_eq!` for `Value`s, but compares them with `diff`
+/// (so the order of object members doesn't matter) and prints the changes on failure.
+#[macro_export]
+macro_rules! assert_json_eq {
+    ($lhs:expr, $rhs:expr $(,)?) => {
+        match (&$lhs, &$rhs) {
+            (lhs, rhs) => {
+                let diff = $crate::Diff::new(lhs, rhs);
+                if !diff.is_empty() {
+                    panic!("assertion `left == right` failed (JSON)\n{}", diff);
+                }
+            }
+        }
+    };
+    ($lhs:expr, $rhs:expr, $($arg:tt)+) => {
+        match (&$lhs, &$rhs) {
+            (lhs, rhs) => {
+                let diff = $crate::Diff::new(lhs, rhs);
+                if !diff.is_empty() {
+                    panic!(
+                        "assertion `left == right` failed (JSON): {}\n{}",
+                        format_args!($($arg)+),
+                        diff
+                    );
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+extern crate std;
+
+#[cfg(test)]
+fn render(
+    lhs: &[u8],
+    rhs: &[u8],
+    f: impl Fn(&Value, &Value) -> std::string::String,
+) -> std::string::String {
+    use crate::parser::Parser;
+
+    let (mut lhs, mut rhs) = (lhs.to_vec(), rhs.to_vec());
+    let lhs = Parser::parse(&mut lhs).unwrap();
+    let rhs = Parser::parse(&mut rhs).unwrap();
+    f(&lhs, &rhs)
+}
+
+#[test]
+fn test_diff() {
+    use std::{format, string::ToString, vec::Vec};
+
+    let changes = render(
+        br#"{"a": 1, "b": [1, 2, 3], "c": {"d/e~": true}, "f": "x", "g": null, "a": 5}"#,
+        br#"{"h": [], "c": {"d/e~": false}, "b": [1.0, 4], "a": 1.5, "f": 1, "g": null}"#,
+        |lhs, rhs| {
+            let mut changes = Vec::new();
+            diff(lhs, rhs, |path, change| {
+                let change = match change {
+                    Change::Added(to) => format!("added {}", to),
+                    Change::Removed(from) => format!("removed {}", from),
+                    Change::Replaced { from, to } => format!("replaced {} {}", from, to),
+                    Change::TypeChanged { from, to } => format!("type changed {} {}", from, to),
+                };
+                changes.push(format!("{} {}", path, change));
+            });
+            changes.join("\n")
+        },
+    );
+    assert_eq!(
+        changes,
+        [
+            "/a replaced 1 1.5",
+            "/b/1 replaced 2 4",
+            "/b/2 removed 3",
+            "/c/d~1e~0 replaced true false",
+            "/f type changed \"x\" 1",
+            "/h added []",
+        ]
+        .join("\n")
+    );
+
+    let changes = render(b"[1, 2]", b"[1, 2, 3, [4]]", |lhs, rhs| {
+        Diff::new(lhs, rhs).to_string()
+    });
+    assert_eq!(changes, "+ /2: 3\n+ /3: [4]\n");
+
+    let changes = render(b"[[], 2, 3, 4]", b"[{}]", |lhs, rhs| {
+        Diff::new(lhs, rhs).to_string()
+    });
+    assert_eq!(
+        changes,
+        "~ /0: [] -> {} (type changed)\n- /1: 2\n- /1: 3\n- /1: 4\n"
+    );
+
+    let changes = render(b"1", b"true", |lhs, rhs| Diff::new(lhs, rhs).to_string());
+    assert_eq!(changes, "~ : 1 -> true (type changed)\n");
+
+    let empty = render(
+        br#"{"a": [1, {"b": null}], "c": 2}"#,
+        br#"{"c": 2.0, "a": [1, {"b": null}]}"#,
+        |lhs, rhs| Diff::new(lhs, rhs).is_empty().to_string(),
+    );
+    assert_eq!(empty, "true");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_changes() {
+    let changes = render(br#"{"a": [1]}"#, br#"{"a": [2], "b": 3}"#, |lhs, rhs| {
+        std::format!("{:?}", changes(lhs, rhs))
+    });
+    assert_eq!(
+        changes,
+        r#"[("/a/0", Replaced { from: Integer(1), to: Integer(2) }), ("/b", Added(Integer(3)))]"#
+    );
+}
+
+#[test]
+fn test_json_patch() {
+    use std::string::ToString;
+
+    let patch = render(
+        br#"{"a": 1, "b\"": [1, 2, 3], "c": {"d": true}}"#,
+        br#"{"a": "1", "b\"": [1], "c": {"d": true, "e": {"f": null}}}"#,
+        |lhs, rhs| JsonPatch::new(lhs, rhs).to_string(),
+    );
+    assert_eq!(
+        patch,
+        r#"[{"op":"replace","path":"/a","value":"1"},{"op":"remove","path":"/b\"/1"},{"op":"remove","path":"/b\"/1"},{"op":"add","path":"/c/e","value":{"f":null}}]"#
+    );
+
+    let patch = render(b"[1]", b"[1.0]", |lhs, rhs| {
+        JsonPatch::new(lhs, rhs).to_string()
+    });
+    assert_eq!(patch, "[]");
+
+    let patch = render(b"[1]", b"{}", |lhs, rhs| {
+        JsonPatch::new(lhs, rhs).to_string()
+    });
+    assert_eq!(patch, r#"[{"op":"replace","path":"","value":{}}]"#);
+}
+
+#[test]
+fn test_assert_json_eq() {
+    use crate::parser::Parser;
+
+    let mut lhs = *br#"{"a": [1, 2], "b": null}"#;
+    let mut rhs = *br#"{"b": null, "a": [1, 2.0]}"#;
+    let lhs = Parser::parse(&mut lhs).unwrap();
+    let rhs = Parser::parse(&mut rhs).unwrap();
+    assert_json_eq!(lhs, rhs);
+    assert_json_eq!(lhs, rhs, "with a {}", "message");
+}
+
+#[test]
+#[should_panic(expected = "assertion `left == right` failed (JSON): golden\n~ /a/1: 2 -> 3\n")]
+fn test_assert_json_eq_failure() {
+    use crate::parser::Parser;
+
+    let mut lhs = *br#"{"a": [1, 2]}"#;
+    let mut rhs = *br#"{"a": [1, 3]}"#;
+    let lhs = Parser::parse(&mut lhs).unwrap();
+    let rhs = Parser::parse(&mut rhs).unwrap();
+    assert_json_eq!(lhs, rhs, "golden");
+}
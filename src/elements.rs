@@ -0,0 +1,318 @@
+use core::ops::Range;
+
+use crate::{
+    lex::skip_ws,
+    parser::ParseError,
+    streaming::{Status, StreamingParser},
+    value::Value,
+};
+
+#[derive(Debug)]
+pub enum ElementStatus<'a> {
+    /// The next element isn't complete yet, call `feed` again once more bytes arrive.
+    NeedMoreData,
+    Element(Value<'a>),
+    /// The closing bracket of the root array has been reached.
+    End,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Open,
+    ValueOrEnd,
+    Value,
+    Element,
+    CommaOrEnd,
+    End,
+    Failed,
+}
+
+enum Progress {
+    NeedMoreData,
+    Element(Range<usize>),
+    End,
+}
+
+/// Yields elements of a root array one at a time as soon as each of them is bitmixed,
+/// without waiting for the rest of the document.
+///
+/// `feed` gets a window of the input that grows as more bytes arrive. Bytes of the elements
+/// that have already been returned can be dropped from the front of the window with `compact`,
+/// so the window only has to fit the largest element.
+pub struct ElementStream {
+    pos: usize,
+    state: State,
+    element: StreamingParser,
+}
+
+impl Default for ElementStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ElementStream {
+    pub fn new() -> Self {
+        Self {
+            pos: 0,
+            state: State::Open,
+            element: StreamingParser::new(),
+        }
+    }
+
+    pub fn feed<'a>(&mut self, window: &'a mut [u8]) -> Result<ElementStatus<'a>, ParseError> {
+        match self.advance(window)? {
+            Progress::NeedMoreData => Ok(ElementStatus::NeedMoreData),
+            Progress::Element(range) => Ok(ElementStatus::Element(decode(&window[range])?)),
+            Progress::End => Ok(ElementStatus::End),
+        }
+    }
+
+    /// Drops the bytes that are no longer needed from the front of `buffer[..filled]`,
+    /// returns the new number of filled bytes. Values returned from `feed` must be
+    /// dropped before calling it.
+    pub fn compact(&mut self, buffer: &mut [u8], filled: usize) -> usize {
+        let processed = self.pos.min(filled);
+        buffer.copy_within(processed..filled, 0);
+        self.pos -= processed;
+        filled - processed
+    }
+
+    fn advance(&mut self, data: &mut [u8]) -> Result<Progress, ParseError> {
+        if self.state == State::Failed {
+            return Err(ParseError::Syntax);
+        }
+        let progress = self.step(data);
+        if progress.is_none() {
+            self.state = State::Failed;
+        }
+        progress.ok_or(ParseError::Syntax)
+    }
+
+    fn step(&mut self, data: &mut [u8]) -> Option<Progress> {
+        loop {
+            if self.state == State::Element {
+                // the element is always fed from its first byte, so that
+                // `compact` can move it without breaking the offsets of `self.element`
+                return match self.element.feed(&mut data[self.pos..]).ok()? {
+                    Status::NeedMoreData => Some(Progress::NeedMoreData),
+                    Status::Complete(_) => {
+                        let start = self.pos;
+                        self.pos += self.element.position();
+                        self.state = State::CommaOrEnd;
+                        Some(Progress::Element(start..self.pos))
+                    }
+                };
+            }
+            if self.state == State::End {
+                return Some(Progress::End);
+            }
+
+            skip_ws(data, &mut self.pos);
+            let Some(&byte) = data.get(self.pos) else {
+                return Some(Progress::NeedMoreData);
+            };
+
+            match (self.state, byte) {
+                (State::Open, b'[') => {
+                    self.pos += 1;
+                    self.state = State::ValueOrEnd;
+                }
+                (State::ValueOrEnd | State::CommaOrEnd, b']') => {
+                    self.pos += 1;
+                    self.state = State::End;
+                }
+                (State::ValueOrEnd | State::Value, _) => {
+                    self.element.reset();
+                    self.state = State::Element;
+                }
+                (State::CommaOrEnd, b',') => {
+                    self.pos += 1;
+                    self.state = State::Value;
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+fn decode(data: &[u8]) -> Result<Value<'_>, ParseError> {
+    Value::from_tlv(data).ok_or(ParseError::Syntax)
+}
+
+#[cfg(feature = "std")]
+pub use reader::{ArrayReader, ReadElementError};
+
+#[cfg(feature = "std")]
+mod reader {
+    extern crate std;
+
+    use super::{decode, ElementStream, Progress};
+    use crate::{parser::ParseError, value::Value};
+    use std::{io, vec::Vec};
+
+    const READ_CHUNK: usize = 8 * 1024;
+
+    #[derive(Debug)]
+    pub enum ReadElementError {
+        Io(io::Error),
+        Parse(ParseError),
+    }
+
+    /// Reads elements of a root array from `reader`, the buffer only
+    /// grows when a single element doesn't fit in it.
+    pub struct ArrayReader<R> {
+        reader: R,
+        buffer: Vec<u8>,
+        filled: usize,
+        stream: ElementStream,
+        done: bool,
+    }
+
+    impl<R: io::Read> ArrayReader<R> {
+        pub fn new(reader: R) -> Self {
+            Self::with_buffer(reader, Vec::new())
+        }
+
+        pub fn with_buffer(reader: R, buffer: Vec<u8>) -> Self {
+            Self {
+                reader,
+                buffer,
+                filled: 0,
+                stream: ElementStream::new(),
+                done: false,
+            }
+        }
+
+        /// Returns the next element, `None` after the end of the array.
+        pub fn next_element(&mut self) -> Option<Result<Value<'_>, ReadElementError>> {
+            if self.done {
+                return None;
+            }
+
+            loop {
+                let progress = self.stream.advance(&mut self.buffer[..self.filled]);
+                match progress {
+                    Ok(Progress::NeedMoreData) => {}
+                    Ok(Progress::Element(range)) => {
+                        return Some(decode(&self.buffer[range]).map_err(ReadElementError::Parse))
+                    }
+                    Ok(Progress::End) => {
+                        self.done = true;
+                        return None;
+                    }
+                    Err(err) => return self.fail(ReadElementError::Parse(err)),
+                }
+
+                self.filled = self.stream.compact(&mut self.buffer, self.filled);
+                if self.filled == self.buffer.len() {
+                    self.buffer.resize(self.filled + READ_CHUNK, 0);
+                }
+                match self.reader.read(&mut self.buffer[self.filled..]) {
+                    // the input ended in the middle of the array
+                    Ok(0) => return self.fail(ReadElementError::Parse(ParseError::Syntax)),
+                    Ok(read) => self.filled += read,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => return self.fail(ReadElementError::Io(err)),
+                }
+            }
+        }
+
+        fn fail(&mut self, error: ReadElementError) -> Option<Result<Value<'_>, ReadElementError>> {
+            self.done = true;
+            Some(Err(error))
+        }
+    }
+
+    #[test]
+    fn test_array_reader_memory_is_bounded() {
+        use std::{format, string::String};
+
+        let mut input = String::from("[");
+        for idx in 0..10_000 {
+            if idx > 0 {
+                input.push_str(", ");
+            }
+            input.push_str(&format!("{{\"id\": {}, \"name\": \"item {}\"}}", idx, idx));
+        }
+        input.push(']');
+
+        let mut reader = ArrayReader::new(input.as_bytes());
+        let mut count = 0;
+        while let Some(element) = reader.next_element() {
+            let Ok(Value::Object(object)) = element else {
+                panic!("expected an object")
+            };
+            assert_eq!(object.len(), 2);
+            count += 1;
+        }
+        assert_eq!(count, 10_000);
+        assert!(reader.buffer.len() <= 2 * READ_CHUNK);
+    }
+
+    #[test]
+    fn test_array_reader_truncated() {
+        let mut reader = ArrayReader::new(&b"[1, [2, 3], \"a"[..]);
+        assert!(matches!(reader.next_element(), Some(Ok(Value::Integer(1)))));
+        assert!(matches!(reader.next_element(), Some(Ok(Value::Array(_)))));
+        assert!(matches!(
+            reader.next_element(),
+            Some(Err(ReadElementError::Parse(ParseError::Syntax)))
+        ));
+        assert!(reader.next_element().is_none());
+    }
+}
+
+#[test]
+fn test_element_stream_with_small_window() {
+    let input = br#" [1, "two", {"three": [3]}, [], -5e-1, null ,true]  "#;
+    let mut window = [0_u8; 20];
+    let mut filled = 0;
+    let mut read = 0;
+    let mut stream = ElementStream::new();
+    let mut elements = 0;
+
+    loop {
+        filled = stream.compact(&mut window, filled);
+        match stream.feed(&mut window[..filled]).unwrap() {
+            ElementStatus::Element(value) => {
+                elements += 1;
+                match elements {
+                    1 => assert!(matches!(value, Value::Integer(1))),
+                    2 => assert!(matches!(value, Value::String(b"two"))),
+                    3 => assert!(matches!(value, Value::Object(object) if object.len() == 1)),
+                    4 => assert!(matches!(value, Value::Array(array) if array.is_empty())),
+                    5 => assert!(matches!(value, Value::Float(f) if f == -0.5)),
+                    6 => assert!(matches!(value, Value::Null)),
+                    7 => assert!(matches!(value, Value::True)),
+                    _ => unreachable!(),
+                }
+            }
+            ElementStatus::NeedMoreData => {
+                // deliver the input 3 bytes at a time
+                let chunk = (input.len() - read).min(3).min(window.len() - filled);
+                assert!(chunk > 0);
+                window[filled..filled + chunk].copy_from_slice(&input[read..read + chunk]);
+                filled += chunk;
+                read += chunk;
+            }
+            ElementStatus::End => break,
+        }
+    }
+    assert_eq!(elements, 7);
+}
+
+#[test]
+fn test_element_stream_errors() {
+    for input in [&b"{}"[..], b"[1 2]", b"[1,]", b"[,1]", b"[[}]"] {
+        let mut data = input.to_vec();
+        let mut stream = ElementStream::new();
+        let result = core::iter::from_fn(|| match stream.feed(&mut data) {
+            Ok(ElementStatus::Element(_)) => Some(Ok(())),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        })
+        .find(Result::is_err);
+        assert_eq!(result, Some(Err(ParseError::Syntax)), "{:?}", input);
+    }
+}
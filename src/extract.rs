@@ -0,0 +1,85 @@
+use crate::{object::Object, value::Value};
+
+impl<'a> Object<'a> {
+    /// Looks up `keys` in a single pass over the members, stops as soon as all of them are found.
+    /// If a key appears more than once the first member wins.
+    pub fn extract<const N: usize>(&self, keys: [&str; N]) -> [Option<Value<'a>>; N] {
+        let mut values = core::array::from_fn(|_| None);
+        let mut missing = N;
+        let mut members = self.iter();
+        while missing > 0 {
+            let Some((key, value)) = members.next() else {
+                break;
+            };
+            if let Some(slot) = slot_of(&keys, &values, key) {
+                values[slot] = Some(value);
+                missing -= 1;
+            }
+        }
+        values
+    }
+
+    /// `Object::extract` that also goes through the remaining members and
+    /// returns the first key that is not one of `keys` as an error.
+    pub fn extract_strict<const N: usize>(
+        &self,
+        keys: [&str; N],
+    ) -> Result<[Option<Value<'a>>; N], &'a [u8]> {
+        let mut values = core::array::from_fn(|_| None);
+        for (key, value) in self.iter() {
+            match slot_of(&keys, &values, key) {
+                Some(slot) => values[slot] = Some(value),
+                None if keys.iter().any(|known| known.as_bytes() == key) => {}
+                None => return Err(key),
+            }
+        }
+        Ok(values)
+    }
+}
+
+// Index of the key that matches and hasn't been found yet
+fn slot_of<const N: usize>(
+    keys: &[&str; N],
+    values: &[Option<Value>; N],
+    key: &[u8],
+) -> Option<usize> {
+    keys.iter()
+        .zip(values)
+        .position(|(known, value)| value.is_none() && known.as_bytes() == key)
+}
+
+#[cfg(test)]
+use crate::tests::parse_object;
+
+#[test]
+fn test_extract() {
+    let mut data = *br#"{"id": 1, "name": "x", "id": 2, "tags": [], "extra": null}"#;
+    let object = parse_object(&mut data);
+
+    let [id, tags, missing, name] = object.extract(["id", "tags", "missing", "name"]);
+    assert!(matches!(id, Some(Value::Integer(1))));
+    assert!(matches!(tags, Some(Value::Array(_))));
+    assert!(missing.is_none());
+    assert!(matches!(name, Some(Value::String(b"x"))));
+
+    let [] = object.extract([]);
+}
+
+#[test]
+fn test_extract_strict() {
+    let mut data = *br#"{"id": 1, "name": "x", "id": 2}"#;
+    let object = parse_object(&mut data);
+    let Ok([id, name, tags]) = object.extract_strict(["id", "name", "tags"]) else {
+        panic!("expected no unknown keys")
+    };
+    assert!(matches!(id, Some(Value::Integer(1))));
+    assert!(matches!(name, Some(Value::String(b"x"))));
+    assert!(tags.is_none());
+
+    let mut data = *br#"{"id": 1, "nmae": "x", "other": 2}"#;
+    let object = parse_object(&mut data);
+    assert!(matches!(
+        object.extract_strict(["id", "name"]),
+        Err(b"nmae")
+    ));
+}
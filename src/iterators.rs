@@ -1,11 +1,34 @@
 use crate::{
-    array::Array, object::Object, skip_zeroes::skip_zeroes, string::String, tlv::DecodeTLV,
+    array::Array,
+    object::Object,
+    skip_zeroes::skip_zeroes,
+    span::{Span, Spans},
+    string::String,
+    tlv::DecodeTLV,
+    validate::{validate_key, validate_value, TlvError},
     value::Value,
 };
 
+// Children of a container are shifted by one byte when its header is written,
+// so a child at `pos` in the data of the container is at `offset + 1 + pos` in the input
+pub(crate) fn decode_at<'a>(
+    data: &'a [u8],
+    pos: &mut usize,
+    offset: Option<usize>,
+) -> Option<Value<'a>> {
+    let value = Value::decode_tlv(data, pos)?;
+    Some(match value {
+        Value::Object(object) => Value::Object(Object { offset, ..object }),
+        Value::Array(array) => Value::Array(Array { offset, ..array }),
+        _ => value,
+    })
+}
+
 pub struct ArrayIterator<'a> {
     data: &'a [u8],
     pos: usize,
+    offset: Option<usize>,
+    last: Option<usize>,
 }
 
 impl<'a> Iterator for ArrayIterator<'a> {
@@ -13,16 +36,27 @@ impl<'a> Iterator for ArrayIterator<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         skip_zeroes(self.data, &mut self.pos);
-        let value = Value::decode_tlv(self.data, &mut self.pos)?;
+        let start = self.offset.map(|offset| offset + 1 + self.pos);
+        let value = decode_at(self.data, &mut self.pos, start)?;
+        self.last = start;
         Some(value)
     }
 }
 
+impl ArrayIterator<'_> {
+    /// Span of the last returned element, see `Value::span`.
+    pub fn span(&self, spans: &Spans) -> Option<Span> {
+        spans.at(self.last?)
+    }
+}
+
 impl<'a> Array<'a> {
     pub fn iter(&self) -> ArrayIterator<'a> {
         ArrayIterator {
             data: self.data,
             pos: 0,
+            offset: self.offset,
+            last: None,
         }
     }
 }
@@ -30,6 +64,8 @@ impl<'a> Array<'a> {
 pub struct ObjectIterator<'a> {
     data: &'a [u8],
     pos: usize,
+    offset: Option<usize>,
+    last: Option<(usize, usize)>,
 }
 
 impl<'a> Iterator for ObjectIterator<'a> {
@@ -40,18 +76,145 @@ impl<'a> Iterator for ObjectIterator<'a> {
         if self.pos >= self.data.len() {
             return None;
         }
+        let key_start = self.offset.map(|offset| offset + 1 + self.pos);
         let key = String::decode_tlv(self.data, &mut self.pos)?;
         skip_zeroes(self.data, &mut self.pos);
-        let value = Value::decode_tlv(self.data, &mut self.pos)?;
+        let start = self.offset.map(|offset| offset + 1 + self.pos);
+        let value = decode_at(self.data, &mut self.pos, start)?;
+        self.last = key_start.zip(start);
         Some((key, value))
     }
 }
 
+impl ObjectIterator<'_> {
+    /// Span of the key of the last returned member, see `Value::span`.
+    pub fn key_span(&self, spans: &Spans) -> Option<Span> {
+        spans.at(self.last?.0)
+    }
+
+    /// Span of the value of the last returned member, see `Value::span`.
+    pub fn span(&self, spans: &Spans) -> Option<Span> {
+        spans.at(self.last?.1)
+    }
+
+    // Where the next member starts in the data of the object, see `Object::iter_from`
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+}
+
 impl<'a> Object<'a> {
     pub fn iter(&self) -> ObjectIterator<'a> {
+        self.iter_from(0)
+    }
+
+    pub(crate) fn iter_from(&self, pos: usize) -> ObjectIterator<'a> {
         ObjectIterator {
+            data: self.data,
+            pos,
+            offset: self.offset,
+            last: None,
+        }
+    }
+}
+
+pub struct TryArrayIterator<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for TryArrayIterator<'a> {
+    type Item = Result<Value<'a>, TlvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        skip_zeroes(self.data, &mut self.pos);
+        if self.pos >= self.data.len() {
+            return None;
+        }
+        let start = self.pos;
+        if let Err(err) = validate_value(self.data, &mut self.pos, 0) {
+            self.pos = self.data.len();
+            return Some(Err(err));
+        }
+        let mut pos = start;
+        Value::decode_tlv(self.data, &mut pos).map(Ok)
+    }
+}
+
+impl<'a> Array<'a> {
+    pub fn try_iter(&self) -> TryArrayIterator<'a> {
+        TryArrayIterator {
+            data: self.data,
+            pos: 0,
+        }
+    }
+}
+
+pub struct TryObjectIterator<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for TryObjectIterator<'a> {
+    type Item = Result<(&'a [u8], Value<'a>), TlvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        skip_zeroes(self.data, &mut self.pos);
+        if self.pos >= self.data.len() {
+            return None;
+        }
+        let start = self.pos;
+        let validated = validate_key(self.data, &mut self.pos)
+            .and_then(|_| validate_value(self.data, &mut self.pos, 0));
+        if let Err(err) = validated {
+            self.pos = self.data.len();
+            return Some(Err(err));
+        }
+        let mut pos = start;
+        let key = String::decode_tlv(self.data, &mut pos)?;
+        skip_zeroes(self.data, &mut pos);
+        let value = Value::decode_tlv(self.data, &mut pos)?;
+        Some(Ok((key, value)))
+    }
+}
+
+impl<'a> Object<'a> {
+    pub fn try_iter(&self) -> TryObjectIterator<'a> {
+        TryObjectIterator {
             data: self.data,
             pos: 0,
         }
     }
 }
+
+#[test]
+fn test_try_iter_stops_on_corrupted_element() {
+    use crate::mask::{ARRAY_MASK, NUMBER_MASK, STRING_MASK};
+
+    let data = [NUMBER_MASK | 1, 0, STRING_MASK | 9, 0, b'a'];
+    let array = Array {
+        data: &data,
+        length: 2,
+        offset: None,
+    };
+    let mut iter = array.try_iter();
+    assert!(matches!(iter.next(), Some(Ok(Value::Integer(1)))));
+    assert!(matches!(
+        iter.next(),
+        Some(Err(TlvError::UnexpectedEnd { pos: 2 }))
+    ));
+    assert!(iter.next().is_none());
+
+    let data = [STRING_MASK | 1, 0, b'k', ARRAY_MASK | 3, 0];
+    let object = Object {
+        data: &data,
+        length: 1,
+        offset: None,
+    };
+    let mut iter = object.try_iter();
+    assert!(matches!(
+        iter.next(),
+        Some(Err(TlvError::UnexpectedEnd { pos: 5 }))
+    ));
+    assert!(iter.next().is_none());
+}
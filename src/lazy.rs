@@ -0,0 +1,547 @@
+use crate::{
+    length::Length,
+    lex::{literal, number_length, skip_ws, string_end, unescaped},
+    mask::{ARRAY_MASK, OBJECT_MASK, TYPE_MASK},
+    parser::Parser,
+    skip_zeroes::skip_zeroes,
+    string::String,
+    tlv::{bitmix_close_container, BitmixToTLV, DecodeTLV},
+    value::Value,
+    ws,
+};
+
+//
+// Lazy documents mix raw JSON and TLV: only the values that have been asked for
+// are bitmixed, everything else stays untouched.
+//
+// A materialized value is marked in its parent by replacing the separator
+// in front of it (which normal bitmixing would zero anyway):
+//   1. ':' of an object member or ',' of an array element becomes DONE
+//   2. '[' of an array with a materialized first element becomes ARRAY_FIRST_DONE
+//
+// Neither byte can appear in valid JSON outside of strings,
+// so the raw scanner knows to skip a TLV value after them.
+//
+// Input with these bytes in it is not valid JSON and is rejected when materialized,
+// but it can confuse the scanner, so `Parser::lazy` is only meant for the original JSON.
+//
+
+const DONE: u8 = 0x01;
+const ARRAY_FIRST_DONE: u8 = 0x02;
+
+/// A value of a document that is only parsed as far as it is accessed,
+/// see `Parser::lazy`.
+pub struct LazyValue<'a> {
+    // the value, preceded by its separator in the parent if `offset` is 1
+    data: &'a mut [u8],
+    offset: usize,
+    materialized: bool,
+}
+
+impl Parser {
+    /// Starts on-demand parsing of `data`. Nothing is parsed upfront:
+    /// `LazyValue::get` and `LazyValue::at` only scan the raw JSON up to the requested member
+    /// (other values are skipped without being validated), and only the values
+    /// that are turned into a `Value` get bitmixed. Once bitmixed they stay TLV,
+    /// so accessing them again doesn't parse anything.
+    pub fn lazy(data: &mut [u8]) -> LazyValue<'_> {
+        LazyValue {
+            data,
+            offset: 0,
+            materialized: false,
+        }
+    }
+}
+
+impl<'a> LazyValue<'a> {
+    /// Looks up a member of an object, `None` if it doesn't exist or this is not an object.
+    pub fn get(&mut self, key: &str) -> Option<LazyValue<'_>> {
+        let found = if self.materialized {
+            find_tlv_child(self.data, self.offset, Some(key.as_bytes()), 0)
+        } else {
+            find_member(self.data, self.offset, key.as_bytes())
+        };
+        Some(self.child(found?))
+    }
+
+    /// Looks up an element of an array, `None` if it doesn't exist or this is not an array.
+    pub fn at(&mut self, index: usize) -> Option<LazyValue<'_>> {
+        let found = if self.materialized {
+            find_tlv_child(self.data, self.offset, None, index)
+        } else {
+            find_element(self.data, self.offset, index)
+        };
+        Some(self.child(found?))
+    }
+
+    /// Bitmixes the value (unless it's been done before) and decodes it.
+    /// If the value turns out to be invalid JSON, scalars are left untouched, but containers
+    /// may be bitmixed halfway and the document shouldn't be accessed anymore.
+    pub fn value(&mut self) -> Option<Value<'_>> {
+        self.materialize()?;
+        Value::from_tlv(&self.data[self.offset..])
+    }
+
+    pub fn into_value(mut self) -> Option<Value<'a>> {
+        self.materialize()?;
+        let data: &'a [u8] = self.data;
+        Value::from_tlv(&data[self.offset..])
+    }
+
+    fn materialize(&mut self) -> Option<()> {
+        if self.materialized {
+            return Some(());
+        }
+        // leading whitespace is only zeroed on success, so that a failed scalar doesn't break the parent
+        let mut pos = self.offset;
+        skip_ws(self.data, &mut pos);
+        let start = pos;
+        bitmix_lazy(self.data, &mut pos)?;
+        self.data[self.offset..start].fill(0);
+
+        if self.offset == 1 {
+            self.data[0] = if self.data[0] == b'[' {
+                ARRAY_FIRST_DONE
+            } else {
+                DONE
+            };
+        }
+        self.materialized = true;
+        Some(())
+    }
+
+    fn child(&mut self, found: Found) -> LazyValue<'_> {
+        LazyValue {
+            data: &mut self.data[found.start..found.end],
+            offset: found.offset,
+            materialized: found.materialized,
+        }
+    }
+}
+
+// Location of a value found in a container, relative to the container's data
+struct Found {
+    start: usize,
+    end: usize,
+    offset: usize,
+    materialized: bool,
+}
+
+// Finds a member of a raw object
+fn find_member(data: &[u8], offset: usize, key: &[u8]) -> Option<Found> {
+    let mut pos = offset;
+    skip_ws(data, &mut pos);
+    if data.get(pos) != Some(&b'{') {
+        return None;
+    }
+    pos += 1;
+    skip_ws(data, &mut pos);
+    if data.get(pos) == Some(&b'}') {
+        return None;
+    }
+
+    loop {
+        if data.get(pos) != Some(&b'"') {
+            return None;
+        }
+        let key_end = string_end(data, pos)?;
+        let matches = unescaped(&data[pos + 1..key_end - 1]).eq(key.iter().copied());
+        pos = key_end;
+        skip_ws(data, &mut pos);
+
+        let separator = pos;
+        let materialized = match data.get(pos)? {
+            b':' => false,
+            &DONE => true,
+            _ => return None,
+        };
+        pos += 1;
+        skip_member_value(data, &mut pos, materialized)?;
+
+        if matches {
+            return Some(Found {
+                start: separator,
+                end: pos,
+                offset: 1,
+                materialized,
+            });
+        }
+
+        skip_ws(data, &mut pos);
+        match data.get(pos)? {
+            b',' => {
+                pos += 1;
+                skip_ws(data, &mut pos);
+            }
+            _ => return None,
+        }
+    }
+}
+
+// Finds an element of a raw array
+fn find_element(data: &[u8], offset: usize, index: usize) -> Option<Found> {
+    let mut pos = offset;
+    skip_ws(data, &mut pos);
+    let mut separator = pos;
+    let mut materialized = match data.get(pos)? {
+        b'[' => false,
+        &ARRAY_FIRST_DONE => true,
+        _ => return None,
+    };
+    pos += 1;
+    if !materialized {
+        skip_ws(data, &mut pos);
+        if data.get(pos) == Some(&b']') {
+            return None;
+        }
+    }
+
+    for idx in 0..=index {
+        skip_member_value(data, &mut pos, materialized)?;
+        if idx == index {
+            return Some(Found {
+                start: separator,
+                end: pos,
+                offset: 1,
+                materialized,
+            });
+        }
+
+        skip_ws(data, &mut pos);
+        separator = pos;
+        materialized = match data.get(pos)? {
+            b',' => false,
+            &DONE => true,
+            _ => return None,
+        };
+        pos += 1;
+    }
+    None
+}
+
+// Finds a member (if `key` is given) or an element of a TLV container
+fn find_tlv_child(data: &[u8], offset: usize, key: Option<&[u8]>, index: usize) -> Option<Found> {
+    let mut pos = offset;
+    skip_zeroes(data, &mut pos);
+    let tag = *data.get(pos)? & TYPE_MASK;
+    if tag
+        != if key.is_some() {
+            OBJECT_MASK
+        } else {
+            ARRAY_MASK
+        }
+        || pos + 1 >= data.len()
+    {
+        return None;
+    }
+    let Length(length) = Length::read(data, pos);
+    pos += 2;
+
+    for idx in 0..length {
+        skip_zeroes(data, &mut pos);
+        let matches = match key {
+            Some(key) => String::decode_tlv(data, &mut pos)? == key,
+            None => idx == index,
+        };
+        skip_zeroes(data, &mut pos);
+        let start = pos;
+        if !Value::skip_tlv(data, &mut pos) {
+            return None;
+        }
+        if matches {
+            return Some(Found {
+                start,
+                end: pos,
+                offset: 0,
+                materialized: true,
+            });
+        }
+    }
+    None
+}
+
+// Skips a value that follows a separator, it's TLV if the separator was a marker
+fn skip_member_value(data: &[u8], pos: &mut usize, materialized: bool) -> Option<()> {
+    if materialized {
+        skip_tlv(data, pos)
+    } else {
+        skip_ws(data, pos);
+        skip_raw(data, pos)
+    }
+}
+
+// Also skips the padding after the value (left by escapes and whitespace),
+// the raw JSON that follows doesn't count zeros as whitespace
+fn skip_tlv(data: &[u8], pos: &mut usize) -> Option<()> {
+    skip_zeroes(data, pos);
+    if !Value::skip_tlv(data, pos) {
+        return None;
+    }
+    skip_zeroes(data, pos);
+    Some(())
+}
+
+// Fast skip of a raw value that only tracks strings and brackets, nothing is validated
+fn skip_raw(data: &[u8], pos: &mut usize) -> Option<()> {
+    match *data.get(*pos)? {
+        b'"' => *pos = string_end(data, *pos)?,
+        b'{' | b'[' | ARRAY_FIRST_DONE => {
+            let mut depth = 0_usize;
+            loop {
+                match *data.get(*pos)? {
+                    b'"' => *pos = string_end(data, *pos)?,
+                    b'{' | b'[' => {
+                        depth += 1;
+                        *pos += 1;
+                    }
+                    b'}' | b']' => {
+                        depth -= 1;
+                        *pos += 1;
+                        if depth == 0 {
+                            return Some(());
+                        }
+                    }
+                    ARRAY_FIRST_DONE => {
+                        depth += 1;
+                        *pos += 1;
+                        skip_tlv(data, pos)?;
+                    }
+                    DONE => {
+                        *pos += 1;
+                        skip_tlv(data, pos)?;
+                    }
+                    _ => *pos += 1,
+                }
+            }
+        }
+        _ => match literal(data, *pos) {
+            Some((_, length)) => *pos += length,
+            None => match number_length(data, *pos) {
+                0 => return None,
+                length => *pos += length,
+            },
+        },
+    }
+    Some(())
+}
+
+// `Value::bitmix_to_tlv` that also accepts containers with materialized values in them
+fn bitmix_lazy(data: &mut [u8], pos: &mut usize) -> Option<()> {
+    ws::skip_ws(data, pos);
+    match *data.get(*pos)? {
+        b'{' => bitmix_container(data, pos, true),
+        b'[' | ARRAY_FIRST_DONE => bitmix_container(data, pos, false),
+        _ => Value::bitmix_to_tlv(data, pos),
+    }
+}
+
+fn bitmix_container(data: &mut [u8], pos: &mut usize, is_object: bool) -> Option<()> {
+    let start = *pos;
+    let (close, mask) = if is_object {
+        (b'}', OBJECT_MASK)
+    } else {
+        (b']', ARRAY_MASK)
+    };
+    let mut materialized = data[start] == ARRAY_FIRST_DONE;
+    let mut length = 0;
+    *pos += 1;
+
+    if !materialized {
+        ws::skip_ws(data, pos);
+        if data.get(*pos) == Some(&close) {
+            *pos += 1;
+            return bitmix_close_container(data, start, *pos, 0, mask);
+        }
+    }
+
+    loop {
+        if is_object {
+            String::bitmix_to_tlv(data, pos)?;
+            ws::skip_ws(data, pos);
+            materialized = match data.get(*pos)? {
+                b':' => false,
+                &DONE => true,
+                _ => return None,
+            };
+            data[*pos] = 0;
+            *pos += 1;
+        }
+
+        if materialized {
+            skip_tlv(data, pos)?;
+        } else {
+            bitmix_lazy(data, pos)?;
+        }
+        length += 1;
+
+        ws::skip_ws(data, pos);
+        match *data.get(*pos)? {
+            byte if byte == close => {
+                *pos += 1;
+                return bitmix_close_container(data, start, *pos, length, mask);
+            }
+            b',' => materialized = false,
+            DONE if !is_object => materialized = true,
+            _ => return None,
+        }
+        data[*pos] = 0;
+        *pos += 1;
+        ws::skip_ws(data, pos);
+    }
+}
+
+#[test]
+fn test_lazy_get_only_touches_the_path() {
+    let mut data =
+        *br#"{"skipped": {"a": "}]\"", "b": [1, {"c": 2}]}, "x": {"y": [10, 20]}, "z": 3}"#;
+    let original = data;
+    let mut root = Parser::lazy(&mut data);
+
+    let mut x = root.get("x").unwrap();
+    let mut y = x.get("y").unwrap();
+    assert!(matches!(y.at(1).unwrap().value(), Some(Value::Integer(20))));
+    assert!(y.at(2).is_none());
+    assert!(root.get("missing").is_none());
+
+    let changed = data
+        .iter()
+        .zip(original.iter())
+        .position(|(a, b)| a != b)
+        .unwrap();
+    // everything up to the "20" (and its separator) is untouched
+    let twenty = original.windows(3).position(|w| w == b"20]").unwrap();
+    assert_eq!(changed, twenty - 2);
+    assert_eq!(&data[..changed], &original[..changed]);
+}
+
+#[test]
+fn test_lazy_repeated_access_and_full_materialization() {
+    use crate::Parser;
+
+    let input = br#" { "a" : [ 1 , "two" , { "b!" : null } ] , "c" : true } "#;
+    let mut data = *input;
+    let mut root = Parser::lazy(&mut data);
+
+    assert!(matches!(
+        root.get("a").unwrap().at(1).unwrap().value(),
+        Some(Value::String(b"two"))
+    ));
+    assert!(matches!(
+        root.get("a").unwrap().at(0).unwrap().value(),
+        Some(Value::Integer(1))
+    ));
+    assert!(matches!(root.get("c").unwrap().value(), Some(Value::True)));
+    // still works with materialized values in front of the requested one
+    assert!(matches!(
+        root.get("a")
+            .unwrap()
+            .at(2)
+            .unwrap()
+            .get("b!")
+            .unwrap()
+            .value(),
+        Some(Value::Null)
+    ));
+    assert!(matches!(
+        root.get("a").unwrap().at(1).unwrap().value(),
+        Some(Value::String(b"two"))
+    ));
+
+    let Some(Value::Object(object)) = root.into_value() else {
+        panic!("expected an object")
+    };
+    assert_eq!(object.len(), 2);
+
+    let mut expected = *input;
+    let expected = Parser::parse(&mut expected).unwrap();
+    let Value::Object(expected) = expected else {
+        unreachable!()
+    };
+    assert_eq!(object.data, expected.data);
+}
+
+#[test]
+fn test_lazy_on_materialized_containers() {
+    let mut data = *br#"{"a": {"b": [1, 2, 3]}}"#;
+    let mut root = Parser::lazy(&mut data);
+    assert!(root.get("a").unwrap().value().is_some());
+    assert!(matches!(
+        root.get("a")
+            .unwrap()
+            .get("b")
+            .unwrap()
+            .at(2)
+            .unwrap()
+            .value(),
+        Some(Value::Integer(3))
+    ));
+    assert!(root.get("a").unwrap().at(0).is_none());
+    assert!(root.get("a").unwrap().get("c").is_none());
+}
+
+#[test]
+fn test_lazy_invalid_values() {
+    let mut data = *br#"{"a": [1, 2,], "b": 01, "c": 3}"#;
+    let mut root = Parser::lazy(&mut data);
+    assert!(root.get("b").unwrap().value().is_none());
+    assert!(matches!(
+        root.get("c").unwrap().value(),
+        Some(Value::Integer(3))
+    ));
+    assert!(root.get("a").unwrap().at(1).unwrap().value().is_some());
+    assert!(root.get("a").unwrap().value().is_none());
+}
+
+#[test]
+fn test_lazy_siblings_after_padded_values() {
+    let mut data = *br#"{"a": "x\ny", "b": 1}"#;
+    let mut root = Parser::lazy(&mut data);
+    assert!(matches!(
+        root.get("a").unwrap().value(),
+        Some(Value::String(b"x\ny"))
+    ));
+    assert!(matches!(
+        root.get("b").unwrap().value(),
+        Some(Value::Integer(1))
+    ));
+    assert!(matches!(root.into_value(), Some(Value::Object(object)) if object.len() == 2));
+
+    let mut data = *br#"{"a": [1 ], "b": 2}"#;
+    let mut root = Parser::lazy(&mut data);
+    assert!(root.get("a").unwrap().value().is_some());
+    assert!(matches!(
+        root.get("b").unwrap().value(),
+        Some(Value::Integer(2))
+    ));
+    assert!(matches!(root.into_value(), Some(Value::Object(object)) if object.len() == 2));
+
+    let mut data = *br#"[ "x\ny" , 5]"#;
+    let mut root = Parser::lazy(&mut data);
+    assert!(root.at(0).unwrap().value().is_some());
+    assert!(matches!(
+        root.at(1).unwrap().value(),
+        Some(Value::Integer(5))
+    ));
+    assert!(matches!(root.into_value(), Some(Value::Array(array)) if array.len() == 2));
+
+    // padded values deeper in a container that is then materialized as a whole
+    let mut data = *br#"{"a": {"b": "\u0041", "c": [ 1 ]}, "d": [{"e": 1 }, 2]}"#;
+    let mut root = Parser::lazy(&mut data);
+    assert!(root.get("a").unwrap().get("b").unwrap().value().is_some());
+    assert!(root.get("d").unwrap().at(0).unwrap().value().is_some());
+    assert!(matches!(
+        root.get("d").unwrap().at(1).unwrap().value(),
+        Some(Value::Integer(2))
+    ));
+    assert!(matches!(
+        root.get("a")
+            .unwrap()
+            .get("c")
+            .unwrap()
+            .at(0)
+            .unwrap()
+            .value(),
+        Some(Value::Integer(1))
+    ));
+    assert!(matches!(root.into_value(), Some(Value::Object(object)) if object.len() == 2));
+}
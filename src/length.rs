@@ -1,6 +1,6 @@
 // We have 5 bytes after the initial mask, 0b11111 is the max value for 5 bits
 // So we end up having 5 + 8 = 13 bytes for length (i.e. 8192(-1) elements can be stored at max)
-const MAX_LENGTH: usize = 2_usize.pow(13);
+pub(crate) const MAX_LENGTH: usize = 2_usize.pow(13);
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct Length(pub(crate) usize);
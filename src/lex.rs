@@ -0,0 +1,182 @@
+// Lexing rules shared by the in-place bitmix pass and the read-only `Tokenizer`
+
+use crate::{
+    simd::{number_run, string_run, ws_run},
+    true_false_null::TrueFalseNull,
+};
+
+pub(crate) fn is_ws(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+pub(crate) fn skip_ws(data: &[u8], pos: &mut usize) {
+    *pos += ws_run(data, *pos);
+}
+
+// Length of the run of number characters at `pos`,
+// the run itself is checked with `is_valid_number`
+pub(crate) fn number_length(data: &[u8], pos: usize) -> usize {
+    number_run(data, pos)
+}
+
+pub(crate) fn literal(data: &[u8], pos: usize) -> Option<(TrueFalseNull, usize)> {
+    if data.get(pos..pos + 4) == Some(b"true") {
+        Some((TrueFalseNull::True, 4))
+    } else if data.get(pos..pos + 5) == Some(b"false") {
+        Some((TrueFalseNull::False, 5))
+    } else if data.get(pos..pos + 4) == Some(b"null") {
+        Some((TrueFalseNull::Null, 4))
+    } else {
+        None
+    }
+}
+
+pub(crate) enum Escape {
+    Byte(u8),
+    Char(char),
+}
+
+// Decodes an escape sequence that starts with a backslash at `pos`,
+// returns it together with the number of bytes it takes
+pub(crate) fn escape(data: &[u8], pos: usize) -> Option<(Escape, usize)> {
+    let byte = match data.get(pos + 1)? {
+        b'"' => b'"',
+        b'\\' => b'\\',
+        b'/' => b'/',
+        b'b' => 0x08,
+        b'f' => 0x0C,
+        b'n' => b'\n',
+        b'r' => b'\r',
+        b't' => b'\t',
+        b'u' => return unicode_escape(data, pos),
+        _ => return None,
+    };
+    Some((Escape::Byte(byte), 2))
+}
+
+fn unicode_escape(data: &[u8], pos: usize) -> Option<(Escape, usize)> {
+    let high = hex4(data, pos + 2)?;
+    if !(0xD800..0xDC00).contains(&high) {
+        return Some((Escape::Char(char::from_u32(high)?), 6));
+    }
+
+    // surrogate pair, the low half must follow as another \uXXXX
+    if data.get(pos + 6..pos + 8) != Some(b"\\u") {
+        return None;
+    }
+    let low = hex4(data, pos + 8)?;
+    if !(0xDC00..0xE000).contains(&low) {
+        return None;
+    }
+    let code_point = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+    Some((Escape::Char(char::from_u32(code_point)?), 12))
+}
+
+fn hex4(data: &[u8], pos: usize) -> Option<u32> {
+    data.get(pos..pos + 4)?
+        .iter()
+        .try_fold(0, |acc, c| Some(acc << 4 | unhex(*c)? as u32))
+}
+
+fn unhex(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+// Bytes of the raw contents of a string (without quotes) with escapes decoded,
+// stops at the first invalid escape
+pub(crate) fn unescaped(raw: &[u8]) -> Unescaped<'_> {
+    Unescaped {
+        raw,
+        pos: 0,
+        buf: [0; 4],
+        buf_pos: 0,
+        buf_len: 0,
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Unescaped<'a> {
+    raw: &'a [u8],
+    pos: usize,
+    buf: [u8; 4],
+    buf_pos: usize,
+    buf_len: usize,
+}
+
+impl Iterator for Unescaped<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.buf_pos < self.buf_len {
+            self.buf_pos += 1;
+            return Some(self.buf[self.buf_pos - 1]);
+        }
+        let byte = *self.raw.get(self.pos)?;
+        if byte != b'\\' {
+            self.pos += 1;
+            return Some(byte);
+        }
+
+        let (escape, length) = escape(self.raw, self.pos)?;
+        self.pos += length;
+        match escape {
+            Escape::Byte(byte) => Some(byte),
+            Escape::Char(char) => {
+                self.buf_len = char.encode_utf8(&mut self.buf).len();
+                self.buf_pos = 1;
+                Some(self.buf[0])
+            }
+        }
+    }
+}
+
+// Finds the end of a string that starts with a quote at `pos`, returns
+// the position right after the closing quote
+pub(crate) fn string_end(data: &[u8], pos: usize) -> Option<usize> {
+    let mut pos = pos + 1;
+    loop {
+        match *data.get(pos)? {
+            b'"' => return Some(pos + 1),
+            b'\\' => pos += escape(data, pos)?.1,
+            0..=0x1F => return None,
+            _ => pos += string_run(data, pos),
+        }
+    }
+}
+
+#[test]
+fn test_escape() {
+    assert!(matches!(escape(br#"\""#, 0), Some((Escape::Byte(b'"'), 2))));
+    assert!(matches!(escape(br"\/", 0), Some((Escape::Byte(b'/'), 2))));
+    assert!(matches!(
+        escape(br"\u00e9", 0),
+        Some((Escape::Char('\u{e9}'), 6))
+    ));
+    assert!(matches!(
+        escape(br"\ud83d\ude00", 0),
+        Some((Escape::Char('\u{1f600}'), 12))
+    ));
+    assert!(escape(br"\ud83d", 0).is_none());
+    assert!(escape(br"\udc00", 0).is_none());
+    assert!(escape(br"\u12g4", 0).is_none());
+    assert!(escape(br"\x", 0).is_none());
+}
+
+#[test]
+fn test_unescaped() {
+    assert!(unescaped(br"a\tb\u00e9\ud83d\ude00").eq("a\tb\u{e9}\u{1f600}".bytes()));
+    assert!(unescaped(br"ab\x").eq(b"ab".iter().copied()));
+}
+
+#[test]
+fn test_string_end() {
+    assert_eq!(string_end(br#""abc" "#, 0), Some(5));
+    assert_eq!(string_end(br#""a\"b""#, 0), Some(6));
+    assert_eq!(string_end(br#""abc"#, 0), None);
+    assert_eq!(string_end(b"\"a\nb\"", 0), None);
+}
@@ -1,24 +1,82 @@
-// #![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 
 mod array;
+mod canonical;
+mod compact;
+mod compare;
+mod cursor;
+mod diff;
+mod elements;
+mod extract;
 mod iterators;
+mod lazy;
 mod length;
+mod lex;
+mod lines;
 mod mask;
+mod merge_patch;
 mod number;
 mod object;
+mod object_index;
+mod options;
 mod parser;
+#[cfg(feature = "std")]
+mod patch;
+mod persisted;
+mod pointer;
+mod projection;
+#[cfg(feature = "std")]
+mod schema;
+mod sequence;
+mod serialize;
+mod simd;
 mod skip_zeroes;
+mod span;
+mod stack;
+mod streaming;
 mod string;
+mod tape;
 mod tlv;
+mod tokenizer;
 mod true_false_null;
+mod validate;
 mod value;
+mod visitor;
 mod ws;
 
 pub use array::Array;
-pub use iterators::{ArrayIterator, ObjectIterator};
+pub use canonical::CanonicalError;
+pub use cursor::Cursor;
+#[cfg(feature = "std")]
+pub use diff::changes;
+pub use diff::{diff, Change, Diff, JsonPatch, Path};
+#[cfg(feature = "std")]
+pub use elements::{ArrayReader, ReadElementError};
+pub use elements::{ElementStatus, ElementStream};
+pub use iterators::{ArrayIterator, ObjectIterator, TryArrayIterator, TryObjectIterator};
+pub use lazy::LazyValue;
+pub use lines::{LineError, Lines};
+#[cfg(feature = "std")]
+pub use lines::{LinesReader, ReadLineError};
+pub use merge_patch::{merge_patch, merge_patches};
 pub use object::Object;
-pub use parser::Parser;
+pub use object_index::ObjectIndex;
+pub use options::{DuplicateKeys, ParserOptions, ParserOptionsBuilder};
+pub use parser::{ParseError, Parser};
+#[cfg(feature = "std")]
+pub use patch::{apply_patch, PatchError};
+pub use persisted::{PersistError, PERSISTED_HEADER_LENGTH};
+pub use projection::Projection;
+#[cfg(feature = "std")]
+pub use schema::{Schema, SchemaError, SchemaErrorKind, ValidationError, ValidationErrorKind};
+pub use sequence::Sequence;
+pub use span::{Span, Spans};
+pub use streaming::{Status, StreamingParser};
+pub use tape::{Tape, TapeIter, TapeNode};
+pub use tokenizer::{Event, Token, TokenizeError, Tokenizer};
+pub use validate::TlvError;
 pub use value::Value;
+pub use visitor::{Control, Visitor};
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,174 @@
+use crate::{
+    lex::is_ws,
+    parser::{ParseError, Parser},
+    value::Value,
+};
+
+/// Error in a newline-delimited stream, `line` is 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineError {
+    pub line: usize,
+    pub error: ParseError,
+}
+
+/// Iterator over the documents of NDJSON / JSON Lines input, see `Parser::parse_lines`.
+pub struct Lines<'a> {
+    rest: &'a mut [u8],
+    line: usize,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Result<Value<'a>, LineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.rest.is_empty() {
+                return None;
+            }
+            let rest = core::mem::take(&mut self.rest);
+            let line = match rest.iter().position(|byte| *byte == b'\n') {
+                Some(end) => {
+                    let (line, tail) = rest.split_at_mut(end);
+                    self.rest = &mut tail[1..];
+                    line
+                }
+                None => rest,
+            };
+            self.line += 1;
+
+            // blank lines are allowed between documents
+            if line.iter().all(|byte| is_ws(*byte)) {
+                continue;
+            }
+
+            let line_number = self.line;
+            return Some(Parser::parse_document(line).map_err(|error| LineError {
+                line: line_number,
+                error,
+            }));
+        }
+    }
+}
+
+impl Parser {
+    /// Parses newline-delimited JSON, every line is bitmixed in place.
+    /// A broken line is reported with its number and doesn't affect the following ones.
+    pub fn parse_lines(data: &mut [u8]) -> Lines<'_> {
+        Lines {
+            rest: data,
+            line: 0,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub use reader::{LinesReader, ReadLineError};
+
+#[cfg(feature = "std")]
+mod reader {
+    extern crate std;
+
+    use super::LineError;
+    use crate::{lex::is_ws, parser::Parser, value::Value};
+    use std::{io, vec::Vec};
+
+    #[derive(Debug)]
+    pub enum ReadLineError {
+        Io(io::Error),
+        Parse(LineError),
+    }
+
+    /// Reads NDJSON documents from `reader` one line at a time, the same
+    /// line buffer is reused for every document.
+    pub struct LinesReader<R> {
+        reader: R,
+        buffer: Vec<u8>,
+        line: usize,
+    }
+
+    impl<R: io::BufRead> LinesReader<R> {
+        pub fn new(reader: R) -> Self {
+            Self::with_buffer(reader, Vec::new())
+        }
+
+        pub fn with_buffer(reader: R, buffer: Vec<u8>) -> Self {
+            Self {
+                reader,
+                buffer,
+                line: 0,
+            }
+        }
+
+        /// Returns the next document, `None` at the end of input.
+        pub fn next_document(&mut self) -> Option<Result<Value<'_>, ReadLineError>> {
+            loop {
+                self.buffer.clear();
+                match self.reader.read_until(b'\n', &mut self.buffer) {
+                    Ok(0) => return None,
+                    Ok(_) => {}
+                    Err(err) => return Some(Err(ReadLineError::Io(err))),
+                }
+                self.line += 1;
+                if self.buffer.iter().all(|byte| is_ws(*byte)) {
+                    continue;
+                }
+
+                let line = self.line;
+                return Some(
+                    Parser::parse_document(&mut self.buffer)
+                        .map_err(|error| ReadLineError::Parse(LineError { line, error })),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_lines_reader() {
+        let input = &b"{\"a\": 1}\r\n\n[1, 2\n\"last\""[..];
+        let mut reader = LinesReader::new(input);
+
+        assert!(matches!(reader.next_document(), Some(Ok(Value::Object(_)))));
+        assert!(matches!(
+            reader.next_document(),
+            Some(Err(ReadLineError::Parse(LineError { line: 3, .. })))
+        ));
+        assert!(matches!(
+            reader.next_document(),
+            Some(Ok(Value::String(b"last")))
+        ));
+        assert!(reader.next_document().is_none());
+    }
+}
+
+#[test]
+fn test_parse_lines() {
+    let mut data = *b"{\"a\": 1}\n[1, 2, 3]\r\n\n  \n\"str\"\nnot json\n{\"b\": [true]} 1\n42";
+    let mut lines = Parser::parse_lines(&mut data);
+
+    assert!(matches!(lines.next(), Some(Ok(Value::Object(_)))));
+    assert!(matches!(lines.next(), Some(Ok(Value::Array(array))) if array.len() == 3));
+    assert!(matches!(lines.next(), Some(Ok(Value::String(b"str")))));
+    assert!(matches!(
+        lines.next(),
+        Some(Err(LineError {
+            line: 6,
+            error: ParseError::Syntax
+        }))
+    ));
+    assert!(matches!(lines.next(), Some(Err(LineError { line: 7, .. }))));
+    assert!(matches!(lines.next(), Some(Ok(Value::Integer(42)))));
+    assert!(lines.next().is_none());
+}
+
+#[test]
+fn test_parse_lines_values_outlive_next() {
+    let mut data = *b"\"a\"\n\"b\"\n";
+    let mut lines = Parser::parse_lines(&mut data);
+    let first = lines.next().unwrap().unwrap();
+    let second = lines.next().unwrap().unwrap();
+    assert!(lines.next().is_none());
+    assert!(matches!(
+        (first, second),
+        (Value::String(b"a"), Value::String(b"b"))
+    ));
+}
@@ -0,0 +1,234 @@
+use core::fmt::{self, Write};
+
+#[cfg(feature = "std")]
+use crate::object_index::ObjectIndex;
+use crate::{
+    object::Object,
+    serialize::{write_string, write_value},
+    skip_zeroes::skip_zeroes,
+    value::Value,
+};
+
+// Layers are merged member by member without building anything in between:
+// every member is written where it first appears, with the value it has after all the layers.
+// Index 0 of a list of layers can be the target, its `null`s are values and not deletions
+
+enum Merged<'a, const N: usize> {
+    Deleted,
+    Value(Value<'a>),
+    Objects([Option<Object<'a>>; N], bool),
+}
+
+// Object of a layer with its keys indexed when the index can be allocated,
+// without it every lookup scans the members
+struct Layer<'a> {
+    object: Object<'a>,
+    #[cfg(feature = "std")]
+    index: Option<ObjectIndex<'a, 'static>>,
+}
+
+impl<'a> Layer<'a> {
+    fn new(object: Object<'a>) -> Self {
+        Layer {
+            object,
+            #[cfg(feature = "std")]
+            index: object.build_owned_index(),
+        }
+    }
+
+    fn find(&self, key: &[u8]) -> Option<usize> {
+        #[cfg(feature = "std")]
+        if let Some(index) = &self.index {
+            return index.find(key);
+        }
+        self.object.find(key)
+    }
+
+    fn get(&self, key: &[u8]) -> Option<Value<'a>> {
+        #[cfg(feature = "std")]
+        if let Some(index) = &self.index {
+            return index.get(key);
+        }
+        self.object.get(key)
+    }
+}
+
+fn merge<'a, const N: usize>(values: &[Option<Value<'a>>; N], target: bool) -> Merged<'a, N> {
+    let Some(last) = values.iter().rposition(Option::is_some) else {
+        return Merged::Deleted;
+    };
+    // anything but an object replaces what was there before it
+    let reset = values[..=last]
+        .iter()
+        .rposition(|value| value.is_some_and(|value| !matches!(value, Value::Object(_))));
+    match reset {
+        Some(idx) if idx == last => match values[idx] {
+            Some(Value::Null) if !(target && idx == 0) => Merged::Deleted,
+            value => value.map_or(Merged::Deleted, Merged::Value),
+        },
+        _ => {
+            let start = reset.map_or(0, |idx| idx + 1);
+            let objects = core::array::from_fn(|idx| match values[idx] {
+                Some(Value::Object(object)) if idx >= start => Some(object),
+                _ => None,
+            });
+            Merged::Objects(objects, target && reset.is_none())
+        }
+    }
+}
+
+fn write_merged<const N: usize>(merged: Merged<'_, N>, out: &mut impl Write) -> fmt::Result {
+    match merged {
+        Merged::Deleted => out.write_str("null"),
+        Merged::Value(value) => write_value(&value, None, out),
+        Merged::Objects(objects, target) => write_objects(&objects, target, out),
+    }
+}
+
+fn write_objects<const N: usize>(
+    objects: &[Option<Object>; N],
+    target: bool,
+    out: &mut impl Write,
+) -> fmt::Result {
+    out.write_char('{')?;
+    let layers = objects.map(|object| object.map(Layer::new));
+    let mut empty = true;
+    for (idx, layer) in layers.iter().enumerate() {
+        let Some(layer) = layer else {
+            continue;
+        };
+        let mut members = layer.object.iter();
+        loop {
+            let mut pos = members.position();
+            skip_zeroes(layer.object.data, &mut pos);
+            let Some((key, _)) = members.next() else {
+                break;
+            };
+            // written where the key first appears, the other members only contribute values
+            if layer.find(key) != Some(pos)
+                || layers[..idx]
+                    .iter()
+                    .flatten()
+                    .any(|other| other.find(key).is_some())
+            {
+                continue;
+            }
+            let values: [_; N] =
+                core::array::from_fn(|idx| layers[idx].as_ref().and_then(|other| other.get(key)));
+            let merged = merge(&values, target);
+            if matches!(merged, Merged::Deleted) {
+                continue;
+            }
+            if !empty {
+                out.write_char(',')?;
+            }
+            empty = false;
+            write_string(key, out)?;
+            out.write_char(':')?;
+            write_merged(merged, out)?;
+        }
+    }
+    out.write_char('}')
+}
+
+/// Applies an RFC 7396 JSON Merge Patch to `target` and writes the result to `out` as compact JSON.
+pub fn merge_patch(target: Value, patch: Value, out: &mut impl Write) -> fmt::Result {
+    merge_patches([target, patch], out)
+}
+
+/// `merge_patch` of every layer onto the result of the ones before it, in a single pass.
+/// The first layer is the target, for example `[base, environment, user]`.
+/// With the `std` feature the keys of every object are indexed, so members are merged in
+/// linear time; without it every lookup scans the members of the object.
+pub fn merge_patches<const N: usize>(layers: [Value; N], out: &mut impl Write) -> fmt::Result {
+    write_merged(merge(&layers.map(Some), true), out)
+}
+
+#[cfg(test)]
+extern crate std;
+
+#[cfg(test)]
+fn merged<const N: usize>(layers: [&str; N]) -> std::string::String {
+    use crate::parser::Parser;
+
+    let mut buffers = layers.map(|layer| std::vec::Vec::from(layer.as_bytes()));
+    let mut values = buffers
+        .iter_mut()
+        .map(|buffer| Parser::parse(buffer).unwrap());
+    let layers: [_; N] = core::array::from_fn(|_| values.next().unwrap());
+    let mut out = std::string::String::new();
+    merge_patches(layers, &mut out).unwrap();
+    out
+}
+
+#[test]
+fn test_merge_patch_rfc_examples() {
+    let cases = [
+        (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+        (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+        (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+        (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+        (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+        (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+        (
+            r#"{"a":{"b":"c"}}"#,
+            r#"{"a":{"b":"d","c":null}}"#,
+            r#"{"a":{"b":"d"}}"#,
+        ),
+        (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+        (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+        (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+        (r#"{"a":"foo"}"#, r#"null"#, r#"null"#),
+        (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+        (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+        (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+        (
+            r#"{}"#,
+            r#"{"a":{"bb":{"ccc":null}}}"#,
+            r#"{"a":{"bb":{}}}"#,
+        ),
+    ];
+    for (target, patch, expected) in cases {
+        assert_eq!(merged([target, patch]), expected, "{} {}", target, patch);
+    }
+}
+
+#[test]
+fn test_merge_patch_layers() {
+    let base = r#"{"db": {"host": "localhost", "port": 5432, "opts": null}, "debug": false, "tags": ["a"]}"#;
+    let env = r#"{"db": {"host": "db.prod", "pool": {"size": 10, "idle": null}}, "tags": null}"#;
+    let user = r#"{"db": {"port": null, "pool": {"size": 20}}, "debug": true, "tags": [null]}"#;
+    assert_eq!(
+        merged([base, env, user]),
+        r#"{"db":{"host":"db.prod","opts":null,"pool":{"size":20}},"debug":true,"tags":[null]}"#
+    );
+    // same as merging one layer at a time
+    let partial = merged([base, env]);
+    assert_eq!(merged([&partial, user]), merged([base, env, user]));
+
+    assert_eq!(
+        merged([r#"{"a": 1}"#, r#"{"a": null}"#, r#"{"a": {"b": null}}"#]),
+        r#"{"a":{}}"#
+    );
+    assert_eq!(merged([r#"{"a": 1}"#, "[]", r#"{"b": 2}"#]), r#"{"b":2}"#);
+    assert_eq!(merged([r#"{"a": 1, "a": 2}"#]), r#"{"a":1}"#);
+    assert_eq!(merged::<0>([]), "null");
+}
+
+#[test]
+fn test_merge_patch_many_keys() {
+    use std::{format, string::String};
+
+    let object = |keys: core::ops::Range<usize>, value: &str| {
+        let members: std::vec::Vec<String> =
+            keys.map(|key| format!(r#""k{}":{}"#, key, value)).collect();
+        format!("{{{}}}", members.join(","))
+    };
+    let base = object(0..1000, "0");
+    let patch = object(500..1500, "null");
+    let user = object(1000..2000, "[1]");
+    let merged = merged([&base, &patch, &user]);
+    let (kept, added) = (object(0..500, "0"), object(1000..2000, "[1]"));
+    let expected = format!("{},{}", &kept[..kept.len() - 1], &added[1..]);
+    assert_eq!(merged, expected);
+}
@@ -24,11 +24,15 @@ impl HeaderByte {
         length: usize,
     ) -> Option<HeaderByteWriteResult> {
         if length == 1 {
-            data[pos] = NUMBER_MASK | char_to_tlv(data[pos]);
+            let digit = match data[pos] {
+                digit @ b'0'..=b'9' => digit - b'0',
+                _ => return None,
+            };
+            data[pos] = NUMBER_MASK | digit;
             return Some(HeaderByteWriteResult { multibyte: false });
         }
 
-        data[pos] = NUMBER_MASK | MULTIBYTE_FLAG | char_to_tlv(data[pos]);
+        data[pos] = NUMBER_MASK | MULTIBYTE_FLAG | char_to_tlv(data[pos])?;
         Some(HeaderByteWriteResult { multibyte: true })
     }
 
@@ -39,33 +43,36 @@ impl HeaderByte {
         if data[pos] & MULTIBYTE_FLAG == 0 {
             return Some(HeaderByteReadResult {
                 multibyte: false,
-                char: singlebyte_tlv_to_char(data[pos]),
+                char: singlebyte_tlv_to_char(data[pos])?,
             });
         }
 
         Some(HeaderByteReadResult {
             multibyte: true,
-            char: multibyte_tlv_to_char(data[pos]),
+            char: multibyte_tlv_to_char(data[pos])?,
         })
     }
 }
 
-fn char_to_tlv(char: u8) -> u8 {
+fn char_to_tlv(char: u8) -> Option<u8> {
     match char {
-        b'-' => MINUS,
-        digit @ b'0'..=b'9' => digit - b'0',
-        _ => panic!("invalid number"),
+        b'-' => Some(MINUS),
+        digit @ b'0'..=b'9' => Some(digit - b'0'),
+        _ => None,
     }
 }
 
-fn singlebyte_tlv_to_char(tlv: u8) -> u8 {
-    b'0' + (tlv & 0b1111)
+fn singlebyte_tlv_to_char(tlv: u8) -> Option<u8> {
+    match tlv & VALUE_MASK {
+        digit @ 0..=9 => Some(b'0' + digit),
+        _ => None,
+    }
 }
 
-fn multibyte_tlv_to_char(tlv: u8) -> u8 {
+fn multibyte_tlv_to_char(tlv: u8) -> Option<u8> {
     match tlv & VALUE_MASK {
-        MINUS => b'-',
-        digit @ 0..=9 => b'0' + digit,
-        _ => panic!("invalid number"),
+        MINUS => Some(b'-'),
+        digit @ 0..=9 => Some(b'0' + digit),
+        _ => None,
     }
 }
@@ -1,85 +1,166 @@
 #[derive(Debug, PartialEq)]
 pub(crate) enum IntOrFloat {
-    Integer {
-        value: i64,
-        negative: bool,
-    },
-    Float {
-        value: f64,
-        digits_after_dot: Option<u32>,
-        negative: bool,
-    },
+    Integer(i64),
+    Float(f64),
 }
 
 use IntOrFloat::*;
 
+// Numbers up to this length are parsed as they are written
+const SHORT_LENGTH: usize = 64;
+// More significant digits than parsing an `f64` ever looks at (768),
+// the ones after them only matter as a sticky nonzero digit
+const MAX_DIGITS: usize = 800;
+
 impl IntOrFloat {
-    pub(crate) fn append(&mut self, digit: u8) {
-        match self {
-            Integer { value, .. } => *value = *value * 10 + (digit as i64),
-            Float {
-                value,
-                digits_after_dot: Some(digits_after_dot),
-                ..
-            } => {
-                *value += (digit as f64) / (10_i32.pow(*digits_after_dot + 1) as f64);
-                *digits_after_dot += 1;
-            }
-            Float { value, .. } => {
-                *value = *value * 10.0 + (digit as f64);
-            }
+    // `chars` is a JSON number of `length` bytes that has already been checked by `is_valid_number`
+    pub(crate) fn parse(chars: impl Iterator<Item = u8>, length: usize) -> Option<Self> {
+        if length > SHORT_LENGTH {
+            // too long for an `i64`
+            return parse_long(chars).map(Float);
         }
-    }
-
-    pub(crate) fn negate(&mut self) {
-        match self {
-            Integer { negative: true, .. } | Float { negative: true, .. } => {
-                panic!("internal error, double negative?")
-            }
-            Integer { negative, .. } | Float { negative, .. } => *negative = true,
+        let mut lexeme = [0; SHORT_LENGTH];
+        for (slot, char) in lexeme.iter_mut().zip(chars) {
+            *slot = char;
         }
-    }
-
-    pub(crate) fn add_dot(&mut self) {
-        match *self {
-            Integer { value, negative } => {
-                *self = Float {
-                    value: value as f64,
-                    digits_after_dot: Some(0),
-                    negative,
-                }
+        let lexeme = core::str::from_utf8(&lexeme[..length]).ok()?;
+        let is_integer = !lexeme.bytes().any(|c| matches!(c, b'.' | b'e' | b'E'));
+        if is_integer {
+            if let Ok(value) = lexeme.parse::<i64>() {
+                return Some(Integer(value));
             }
-            Float { .. } => panic!("internal error, double dot?"),
         }
+        lexeme.parse::<f64>().ok().map(Float)
     }
 
+    #[cfg(test)]
     pub(crate) fn unwrap_int(self) -> i64 {
         match self {
-            Integer {
-                value,
-                negative: false,
-            } => value,
-            Integer {
-                value,
-                negative: true,
-            } => -value,
+            Integer(value) => value,
             _ => panic!("not an integer"),
         }
     }
 
+    #[cfg(test)]
     pub(crate) fn unwrap_float(self) -> f64 {
         match self {
-            Float {
-                value,
-                negative: false,
-                ..
-            } => value,
-            Float {
-                value,
-                negative: true,
-                ..
-            } => -value,
+            Float(value) => value,
             _ => panic!("not a float"),
         }
     }
 }
+
+// Rewrites the number as `-0.DDDDe-X` with at most `MAX_DIGITS` significant digits
+// and a `1` after them when any of the dropped ones isn't zero, which rounds the same way
+fn parse_long(chars: impl Iterator<Item = u8>) -> Option<f64> {
+    let mut lexeme = [0; MAX_DIGITS + 32];
+    let mut length = 0;
+    let mut push = |char: u8| {
+        lexeme[length] = char;
+        length += 1;
+    };
+
+    let mut chars = chars.peekable();
+    if chars.next_if_eq(&b'-').is_some() {
+        push(b'-');
+    }
+    push(b'0');
+    push(b'.');
+
+    let mut digits = 0_usize;
+    // position of the decimal point relative to the first significant digit
+    let mut point = 0_i64;
+    let mut fraction = false;
+    let mut sticky = false;
+    for char in chars.by_ref() {
+        match char {
+            b'0' if digits == 0 => point -= fraction as i64,
+            b'0'..=b'9' => {
+                point += !fraction as i64;
+                if digits < MAX_DIGITS {
+                    push(char);
+                } else {
+                    sticky |= char != b'0';
+                }
+                digits += 1;
+            }
+            b'.' => fraction = true,
+            _ => break,
+        }
+    }
+    if digits == 0 {
+        push(b'0');
+    }
+    if sticky {
+        push(b'1');
+    }
+
+    let negative = chars.next_if(|char| matches!(char, b'+' | b'-')) == Some(b'-');
+    let mut exponent = 0_i64;
+    for char in chars {
+        exponent = exponent
+            .saturating_mul(10)
+            .saturating_add((char - b'0') as i64);
+    }
+    if negative {
+        exponent = -exponent;
+    }
+    // far past the range of `f64` either way
+    let exponent = exponent.saturating_add(point).clamp(-999_999, 999_999);
+
+    push(b'e');
+    if exponent < 0 {
+        push(b'-');
+    }
+    let mut magnitude = exponent.unsigned_abs();
+    let mut divisor = 100_000;
+    while divisor > 1 && magnitude < divisor {
+        divisor /= 10;
+    }
+    while divisor > 0 {
+        push(b'0' + (magnitude / divisor) as u8);
+        magnitude %= divisor;
+        divisor /= 10;
+    }
+
+    core::str::from_utf8(&lexeme[..length]).ok()?.parse().ok()
+}
+
+// -?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?
+pub(crate) fn is_valid_number(lexeme: &[u8]) -> bool {
+    let mut pos = 0;
+    let digits = |pos: &mut usize| {
+        let start = *pos;
+        while lexeme.get(*pos).is_some_and(u8::is_ascii_digit) {
+            *pos += 1;
+        }
+        *pos - start
+    };
+
+    if lexeme.get(pos) == Some(&b'-') {
+        pos += 1;
+    }
+    match lexeme.get(pos) {
+        Some(b'0') => pos += 1,
+        Some(b'1'..=b'9') => {
+            digits(&mut pos);
+        }
+        _ => return false,
+    }
+    if lexeme.get(pos) == Some(&b'.') {
+        pos += 1;
+        if digits(&mut pos) == 0 {
+            return false;
+        }
+    }
+    if matches!(lexeme.get(pos), Some(b'e' | b'E')) {
+        pos += 1;
+        if matches!(lexeme.get(pos), Some(b'+' | b'-')) {
+            pos += 1;
+        }
+        if digits(&mut pos) == 0 {
+            return false;
+        }
+    }
+    pos == lexeme.len()
+}
@@ -2,7 +2,7 @@
 mod tests;
 
 mod int_or_float;
-pub(crate) use int_or_float::IntOrFloat;
+pub(crate) use int_or_float::{is_valid_number, IntOrFloat};
 
 mod header_byte;
 use header_byte::HeaderByte;
@@ -10,52 +10,59 @@ use header_byte::HeaderByte;
 mod non_header_byte;
 use non_header_byte::NonHeaderByte;
 
-use crate::tlv::{BitmixToTLV, DecodeTLV};
+use crate::{
+    lex::number_length,
+    options::Context,
+    parser::ParseError,
+    tlv::{BitmixToTLV, DecodeTLV},
+};
 
-use self::non_header_byte::{NonHeaderByteChar, NonHeaderByteReadResult};
+use self::non_header_byte::NonHeaderByteReadResult;
 
 //
 // format: 000YVVVV where:
 //   1. 000 - 3 bits for variant
 //   2. Y - 1 bit to indicate if the number is multibyte
-//   3. VVVV - 4 bits for the value (10 digits, "-", ceil(log2(11)) = 4):
+//   3. VVVV - 4 bits for the value (10 digits, "-", "e", ".", "+", ceil(log2(14)) = 4):
 //     0-9 = 0-9
 //     "-" = 10
-//     "e" = 11
+//     "e" = 11 ("E" is stored as "e")
 //     "." = 12
+//     "+" = 13
 //
 // if Y is 0, the number is a single byte
 // if Y is 1, the number is multibyte and the length is a part of the next (1+) byte(s)
-//   writed as 0b1LLLVVVV where:
-//     1 - 1 bit to indicate if the length is there
-//     LLL - 3 bits for the length
+//   writed as 0bCLLLVVVV where:
+//     C - 1 bit to indicate that the next byte has more bits of the length
+//     LLL - 3 bits for the length (least significant bits go first)
 //     VVVV - 4 bits for the value
+//   bytes after the last length chunk have C = 0 and LLL = 0
+//
+// the continuation bit is what tells where the length ends: a length bit on every chunk
+// can't tell a 2-byte number from a 10-byte one when the byte after it has the top bit set
 //
 
 pub(crate) const MINUS: u8 = 0b1010; // 10
 pub(crate) const EXPONENT: u8 = 0b1011; // 11
 pub(crate) const DOT: u8 = 0b1100; // 12
+pub(crate) const PLUS: u8 = 0b1101; // 13
 
 pub(crate) const VALUE_MASK: u8 = 0b0000_1111;
 
 pub(crate) struct Number;
 
 impl BitmixToTLV for Number {
-    fn bitmix_to_tlv(data: &mut [u8], pos: &mut usize) -> Option<()> {
-        let mut region_size = 0;
-        while region_size + *pos < data.len() {
-            if matches!(
-                data[region_size + *pos],
-                b'-' | b'0'..=b'9' | b'.' | b'e' | b'E'
-            ) {
-                region_size += 1;
-            } else {
-                break;
-            }
-        }
+    fn bitmix_to_tlv_with(data: &mut [u8], pos: &mut usize, ctx: &mut Context) -> Option<()> {
+        let region_size = number_length(data, *pos);
         if region_size == 0 {
             return None;
         }
+        if region_size > ctx.options.max_number_length {
+            return ctx.fail(ParseError::NumberTooLong);
+        }
+        if !is_valid_number(&data[*pos..*pos + region_size]) {
+            return None;
+        }
 
         let header = HeaderByte::write(data, *pos, region_size)?;
 
@@ -66,7 +73,7 @@ impl BitmixToTLV for Number {
         let mut length_left_to_write = region_size;
         for idx in 1..region_size {
             length_left_to_write =
-                NonHeaderByte::write(data, *pos + idx, length_left_to_write).length_left;
+                NonHeaderByte::write(data, *pos + idx, length_left_to_write)?.length_left;
         }
 
         *pos += region_size;
@@ -78,69 +85,59 @@ impl DecodeTLV<'_> for Number {
     type ReturnType = IntOrFloat;
 
     fn decode_tlv(data: &[u8], pos: &mut usize) -> Option<Self::ReturnType> {
-        if *pos >= data.len() {
+        let length = Number::length(data, *pos)?;
+        let result = IntOrFloat::parse(Number::chars(data, *pos, length), length)?;
+        *pos += length;
+        Some(result)
+    }
+}
+
+impl Number {
+    // Returns the length of the number at `pos` after checking that all of its bytes hold characters
+    pub(crate) fn length(data: &[u8], pos: usize) -> Option<usize> {
+        if pos >= data.len() {
             return None;
         }
 
-        let header: header_byte::HeaderByteReadResult = HeaderByte::read(data, *pos)?;
-
+        let header = HeaderByte::read(data, pos)?;
         if !header.multibyte {
-            *pos += 1;
-            return Some(IntOrFloat::Integer {
-                value: (header.char - b'0') as i64,
-                negative: false,
-            });
-        }
-
-        let mut result;
-
-        if header.char == b'-' {
-            result = IntOrFloat::Integer {
-                value: 0,
-                negative: true,
-            };
-        } else {
-            result = IntOrFloat::Integer {
-                value: (header.char - b'0') as i64,
-                negative: false,
-            };
+            return Some(1);
         }
 
         let mut length = 0;
+        let mut shift = 0;
         let mut idx = 1;
-        let mut read_total = 1;
-
         loop {
-            let NonHeaderByteReadResult { length_part, char } =
-                NonHeaderByte::read(data, *pos + idx);
-            read_total += 1;
-
-            if let Some(l) = length_part {
-                length |= (l as usize) << (3 * (idx - 1));
-            }
-
-            match char {
-                NonHeaderByteChar::Minus { .. } => {
-                    result.negate();
-                }
-                NonHeaderByteChar::Exponent { .. } => {
-                    panic!("exponents are not supported yet")
-                }
-                NonHeaderByteChar::Dot { .. } => {
-                    result.add_dot();
-                }
-                NonHeaderByteChar::Digit { char, .. } => {
-                    result.append(char - b'0');
-                }
+            if shift >= usize::BITS {
+                return None;
             }
+            let NonHeaderByteReadResult {
+                length_part,
+                more_length,
+                ..
+            } = NonHeaderByte::read(data, pos + idx)?;
+            length |= (length_part as usize) << shift;
+            shift += 3;
             idx += 1;
-
-            if idx >= length {
+            if !more_length {
                 break;
             }
         }
 
-        *pos += read_total;
-        Some(result)
+        if length < idx || length > data.len() - pos {
+            return None;
+        }
+        for idx in idx..length {
+            NonHeaderByte::read(data, pos + idx)?;
+        }
+        Some(length)
+    }
+
+    // Restores the original characters of the number at `pos` that `length` has checked
+    pub(crate) fn chars(data: &[u8], pos: usize, length: usize) -> impl Iterator<Item = u8> + '_ {
+        (0..length).filter_map(move |idx| match idx {
+            0 => HeaderByte::read(data, pos).map(|header| header.char),
+            _ => NonHeaderByte::read(data, pos + idx).map(|byte| byte.char),
+        })
     }
 }
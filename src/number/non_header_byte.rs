@@ -1,6 +1,6 @@
-use crate::number::{DOT, EXPONENT, MINUS, VALUE_MASK};
+use crate::number::{DOT, EXPONENT, MINUS, PLUS, VALUE_MASK};
 
-const HAS_LENGTH_MASK: u8 = 0b1000_0000;
+const MORE_LENGTH_MASK: u8 = 0b1000_0000;
 const LENGTH_MASK: u8 = 0b0111_0000;
 
 pub(crate) struct NonHeaderByte;
@@ -10,49 +10,48 @@ pub(crate) struct NonHeaderByteWriteResult {
 }
 
 pub(crate) struct NonHeaderByteReadResult {
-    pub(crate) length_part: Option<u8>,
-    pub(crate) char: NonHeaderByteChar,
-}
-
-pub(crate) enum NonHeaderByteChar {
-    Digit { char: u8 },
-    Dot,
-    Exponent,
-    Minus,
+    pub(crate) length_part: u8,
+    pub(crate) more_length: bool,
+    pub(crate) char: u8,
 }
 
 impl NonHeaderByte {
-    pub(crate) fn write(data: &mut [u8], pos: usize, length: usize) -> NonHeaderByteWriteResult {
-        let mut length_component = 0;
-        if length != 0 {
-            length_component = ((length % (2 << 3)) << 4) as u8 | HAS_LENGTH_MASK
-        };
+    pub(crate) fn write(
+        data: &mut [u8],
+        pos: usize,
+        length: usize,
+    ) -> Option<NonHeaderByteWriteResult> {
+        let length_left = length >> 3;
+        let mut length_component = ((length & 0b111) << 4) as u8;
+        if length_left != 0 {
+            length_component |= MORE_LENGTH_MASK;
+        }
         let value_component = match data[pos] {
             b'-' => MINUS,
-            b'e' => EXPONENT,
+            b'+' => PLUS,
+            b'e' | b'E' => EXPONENT,
             b'.' => DOT,
-            b'0'..=b'9' => data[pos] - b'0',
-            _ => panic!("invalid number"),
+            digit @ b'0'..=b'9' => digit - b'0',
+            _ => return None,
         };
         data[pos] = length_component | value_component;
-        NonHeaderByteWriteResult {
-            length_left: length >> 3,
-        }
+        Some(NonHeaderByteWriteResult { length_left })
     }
 
-    pub(crate) fn read(data: &[u8], pos: usize) -> NonHeaderByteReadResult {
-        let value = data[pos] & VALUE_MASK;
-        let mut length_part = None;
-        if data[pos] & HAS_LENGTH_MASK == HAS_LENGTH_MASK {
-            length_part = Some((data[pos] & LENGTH_MASK) >> 4)
-        }
-        let char = match value {
-            MINUS => NonHeaderByteChar::Minus,
-            EXPONENT => NonHeaderByteChar::Exponent,
-            DOT => NonHeaderByteChar::Dot,
-            0..=9 => NonHeaderByteChar::Digit { char: b'0' + value },
-            _ => panic!("invalid number: {}", value),
+    pub(crate) fn read(data: &[u8], pos: usize) -> Option<NonHeaderByteReadResult> {
+        let byte = *data.get(pos)?;
+        let char = match byte & VALUE_MASK {
+            MINUS => b'-',
+            PLUS => b'+',
+            EXPONENT => b'e',
+            DOT => b'.',
+            digit @ 0..=9 => b'0' + digit,
+            _ => return None,
         };
-        NonHeaderByteReadResult { length_part, char }
+        Some(NonHeaderByteReadResult {
+            length_part: (byte & LENGTH_MASK) >> 4,
+            more_length: byte & MORE_LENGTH_MASK != 0,
+            char,
+        })
     }
 }
@@ -51,7 +51,7 @@ fn test_69() {
     let mut pos = 1;
     Number::bitmix_to_tlv(&mut data, &mut pos).unwrap();
     assert_eq!(pos, 3);
-    assert_eq!(data, [b' ', 0b001_10110, 0b1010_1001]);
+    assert_eq!(data, [b' ', 0b001_10110, 0b0010_1001]);
 
     pos = 1;
     let value = Number::decode_tlv(&data, &mut pos).unwrap();
@@ -70,8 +70,8 @@ fn test_1234567890987654321() {
         [
             b' ',
             0b001_10001, // 000 = mask, 1 = multibyte, value = 0b001 = 1
-            0b1011_0010, // 0b0011 = length, value = 2
-            0b1010_0011, // 0b0010 = length, value = 3
+            0b1011_0010, // 1 = more length, 0b011 = length, value = 2
+            0b0010_0011, // 0 = no more length, 0b010 = length, value = 3
             0b0000_0100, // 0b0000 = length, value = 4
             0b0000_0101, // 0b0000 = length, value = 5
             0b0000_0110, // 0b0000 = length, value = 6
@@ -108,7 +108,7 @@ fn test_minus_1() {
         [
             b' ',
             0b001_11010, // 000 = mask, 1 = multibyte, value = 0b1010 = MINUS
-            0b1010_0001, // 0010 = length = 2, value = 1
+            0b0010_0001, // 0010 = length = 2, value = 1
         ]
     );
 
@@ -129,7 +129,7 @@ fn test_two_point_three() {
         [
             b' ',
             0b001_10010, // 000 = mask, 1 = multibyte, value = 0b0010 = 2
-            0b1011_1100, // 0011 = length = 3, value = 12 = DOT
+            0b0011_1100, // 0011 = length = 3, value = 12 = DOT
             0b0000_0011, // 0000 = length = 0, value = 3
         ]
     );
@@ -139,3 +139,130 @@ fn test_two_point_three() {
     assert_eq!(pos, 4);
     assert_eq!(value.unwrap_float(), 2.3);
 }
+
+#[test]
+fn test_12345678() {
+    let mut data = *b" 12345678"; // 8 bytes = 0b1_000
+    let mut pos = 1;
+    Number::bitmix_to_tlv(&mut data, &mut pos).unwrap();
+    assert_eq!(pos, 9);
+    assert_eq!(
+        data,
+        [
+            b' ',
+            0b001_10001, // 000 = mask, 1 = multibyte, value = 1
+            0b1000_0010, // 1 = more length, 0b000 = length, value = 2
+            0b0001_0011, // 0 = no more length, 0b001 = length, value = 3
+            0b0000_0100,
+            0b0000_0101,
+            0b0000_0110,
+            0b0000_0111,
+            0b0000_1000,
+        ]
+    );
+
+    pos = 1;
+    let value = Number::decode_tlv(&data, &mut pos).unwrap();
+    assert_eq!(pos, 9);
+    assert_eq!(value.unwrap_int(), 12345678);
+}
+
+#[test]
+fn test_1234567890() {
+    let mut data = *b" 1234567890"; // 10 bytes = 0b1_010
+    let mut pos = 1;
+    Number::bitmix_to_tlv(&mut data, &mut pos).unwrap();
+    assert_eq!(pos, 11);
+
+    pos = 1;
+    let value = Number::decode_tlv(&data, &mut pos).unwrap();
+    assert_eq!(pos, 11);
+    assert_eq!(value.unwrap_int(), 1234567890);
+}
+
+#[test]
+fn test_exponent() {
+    let mut data = *b" -1.5E+3";
+    let mut pos = 1;
+    Number::bitmix_to_tlv(&mut data, &mut pos).unwrap();
+    assert_eq!(pos, 8);
+
+    pos = 1;
+    let value = Number::decode_tlv(&data, &mut pos).unwrap();
+    assert_eq!(pos, 8);
+    assert_eq!(value.unwrap_float(), -1500.0);
+}
+
+#[test]
+fn test_float_precision() {
+    let mut data = *b" 0.1000000000000000055511151231257827";
+    let mut pos = 1;
+    Number::bitmix_to_tlv(&mut data, &mut pos).unwrap();
+
+    pos = 1;
+    let value = Number::decode_tlv(&data, &mut pos).unwrap();
+    assert_eq!(value.unwrap_float(), 0.1);
+}
+
+#[test]
+fn test_invalid() {
+    for input in [
+        &b" -"[..],
+        b" 01",
+        b" 1.",
+        b" 1e",
+        b" --1",
+        b" 1.2.3",
+        b" +1",
+    ] {
+        let mut data = input.to_vec();
+        let mut pos = 1;
+        assert!(Number::bitmix_to_tlv(&mut data, &mut pos).is_none());
+    }
+}
+
+#[test]
+fn test_long_number() {
+    let mut data = [b'1'; 301];
+    data[0] = b' ';
+    let mut pos = 1;
+    Number::bitmix_to_tlv(&mut data, &mut pos).unwrap();
+    assert_eq!(pos, 301);
+
+    pos = 1;
+    let value = Number::decode_tlv(&data, &mut pos).unwrap();
+    assert_eq!(pos, 301);
+    let expected: f64 = core::str::from_utf8(&[b'1'; 300]).unwrap().parse().unwrap();
+    assert_eq!(value.unwrap_float(), expected);
+}
+
+#[test]
+fn test_long_number_rounding() {
+    // 2^53 + 1 is halfway between two floats, only the last digit rounds it up
+    let mut data = [b'0'; 1200];
+    data[..18].copy_from_slice(b" 9007199254740993.");
+    data[1199] = b'1';
+    let mut pos = 1;
+    Number::bitmix_to_tlv(&mut data, &mut pos).unwrap();
+    assert_eq!(pos, 1200);
+
+    pos = 1;
+    let value = Number::decode_tlv(&data, &mut pos).unwrap();
+    assert_eq!(value.unwrap_float(), 9007199254740994.0);
+
+    for input in [
+        &b" -0.000000000000000000000000000000000000000000000000000000000000000000025e2"[..],
+        b" 0.0000000000000000000000000000000000000000000000000000000000000000000000e99999999999999999999",
+        b" 1000000000000000000000000000000000000000000000000000000000000000000000e-70",
+        b" 12345678901234567890123456789012345678901234567890123456789012345678e999",
+    ] {
+        let mut data = input.to_vec();
+        let mut pos = 1;
+        Number::bitmix_to_tlv(&mut data, &mut pos).unwrap();
+        pos = 1;
+        let value = Number::decode_tlv(&data, &mut pos).unwrap();
+        assert_eq!(pos, input.len());
+        let expected: f64 = core::str::from_utf8(&input[1..]).unwrap().parse().unwrap();
+        assert_eq!(value.unwrap_float(), expected);
+    }
+}
@@ -1,21 +1,40 @@
+use core::ops::Range;
+
 use crate::{
-    length::Length,
+    length::{Length, MAX_LENGTH},
     mask::{OBJECT_MASK, TYPE_MASK},
+    options::{Context, DuplicateKeys},
+    parser::ParseError,
     skip_zeroes::skip_zeroes,
     string::String,
-    tlv::{bitmix_consume_byte, BitmixToTLV, DecodeTLV},
+    tlv::{bitmix_close_container, bitmix_consume_byte, BitmixToTLV, DecodeTLV},
     value::Value,
     ws::skip_ws,
 };
 
-#[derive(Debug)]
+#[derive(Clone, Copy)]
 pub struct Object<'a> {
     pub(crate) data: &'a [u8],
+    pub(crate) length: usize,
+    // position of the opening bracket in the input of `Parser::parse`,
+    // `None` when the value was decoded from TLV that can't be traced back to it
+    pub(crate) offset: Option<usize>,
 }
 
-fn bitmix_pair(data: &mut [u8], pos: &mut usize) -> Option<()> {
+fn bitmix_pair(
+    data: &mut [u8],
+    pos: &mut usize,
+    length: &mut usize,
+    ctx: &mut Context,
+) -> Option<()> {
+    if *length >= ctx.options.max_keys.min(MAX_LENGTH - 1) {
+        return ctx.fail(ParseError::TooManyKeys);
+    }
+
     // key
-    String::bitmix_to_tlv(data, pos)?;
+    ctx.with_span(data, pos, |data, pos, ctx| {
+        String::bitmix_to_tlv_with(data, pos, ctx)
+    })?;
     skip_ws(data, pos);
 
     // ":"
@@ -25,12 +44,18 @@ fn bitmix_pair(data: &mut [u8], pos: &mut usize) -> Option<()> {
 
     // value
     skip_ws(data, pos);
-    Value::bitmix_to_tlv(data, pos)?;
+    Value::bitmix_to_tlv_with(data, pos, ctx)?;
+    *length += 1;
 
     Some(())
 }
 
-fn bitmix_pair_list_and_close(data: &mut [u8], pos: &mut usize, length: &mut usize) -> Option<()> {
+fn bitmix_pair_list_and_close(
+    data: &mut [u8],
+    pos: &mut usize,
+    length: &mut usize,
+    ctx: &mut Context,
+) -> Option<()> {
     skip_ws(data, pos);
 
     if bitmix_consume_byte::<b'}'>(data, pos) {
@@ -38,49 +63,173 @@ fn bitmix_pair_list_and_close(data: &mut [u8], pos: &mut usize, length: &mut usi
         return Some(());
     }
 
-    bitmix_pair(data, pos)?;
-    *length += 1;
+    bitmix_pair(data, pos, length, ctx)?;
 
     while *pos < data.len() {
         skip_ws(data, pos);
 
         if bitmix_consume_byte::<b'}'>(data, pos) {
             return Some(());
-        } else if data[*pos] == b',' {
-            data[*pos] = 0;
-            *pos += 1;
+        } else if bitmix_consume_byte::<b','>(data, pos) {
             skip_ws(data, pos);
 
-            bitmix_pair(data, pos)?;
-            *length += 1;
+            bitmix_pair(data, pos, length, ctx)?;
+        } else {
+            return None;
         }
     }
 
     None
 }
 
+// Range of the next pair in `data[..end]` that's already in TLV, zeroed pairs are skipped
+fn next_pair(data: &[u8], pos: &mut usize, end: usize) -> Option<Range<usize>> {
+    skip_zeroes(data, pos);
+    if *pos >= end {
+        return None;
+    }
+    let start = *pos;
+    if !String::skip_tlv(data, pos) {
+        return None;
+    }
+    skip_zeroes(data, pos);
+    if !Value::skip_tlv(data, pos) {
+        return None;
+    }
+    Some(start..*pos)
+}
+
+// Key of the pair at `pos` in `data`, zeroes before it are skipped
+fn key_at(data: &[u8], pos: u32) -> &[u8] {
+    let mut pos = pos as usize;
+    skip_zeroes(data, &mut pos);
+    String::decode_tlv(data, &mut pos).unwrap_or_default()
+}
+
+// Sorts the positions of pairs in `data` by key,
+// pairs with the same key end up next to each other in the order they are in `data`
+fn sort_by_key(data: &[u8], pairs: &mut [u32]) {
+    pairs.sort_unstable_by(|&a, &b| key_at(data, a).cmp(key_at(data, b)).then(a.cmp(&b)));
+}
+
+// Applies the duplicate keys policy to the pairs of an object between `start` and `end`,
+// the pairs are already in TLV, the ones that are dropped get zeroed.
+// The positions of the pairs are sorted by key in the scratch table, so duplicates are neighbours
+fn dedup_pairs(
+    data: &mut [u8],
+    start: usize,
+    end: usize,
+    length: &mut usize,
+    ctx: &mut Context,
+) -> Option<()> {
+    let policy = ctx.options.duplicate_keys;
+    if policy == DuplicateKeys::KeepAll {
+        return Some(());
+    }
+    if ctx.scratch.len() < *length || u32::try_from(end - start).is_err() {
+        return ctx.fail(ParseError::ScratchTooSmall);
+    }
+
+    let pairs = &mut ctx.scratch[..*length];
+    let mut pos = start;
+    for slot in pairs.iter_mut() {
+        *slot = (next_pair(data, &mut pos, end)?.start - start) as u32;
+    }
+    sort_by_key(&data[start..end], pairs);
+
+    let mut idx = 0;
+    while idx < pairs.len() {
+        let object = &data[start..end];
+        let key = key_at(object, pairs[idx]);
+        let mut run = idx + 1;
+        while run < pairs.len() && key_at(object, pairs[run]) == key {
+            run += 1;
+        }
+        if run - idx > 1 && policy == DuplicateKeys::Error {
+            return ctx.fail(ParseError::DuplicateKey);
+        }
+        let kept = match policy {
+            DuplicateKeys::LastWins => run - 1,
+            _ => idx,
+        };
+        for dropped in (idx..run).filter(|&other| other != kept) {
+            let mut pos = start + pairs[dropped] as usize;
+            let pair = next_pair(data, &mut pos, end)?;
+            data[pair].fill(0);
+            *length -= 1;
+        }
+        idx = run;
+    }
+
+    Some(())
+}
+
+impl<'a> Object<'a> {
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Value of the first member with `key`, keys are compared as they are stored (unescaped).
+    /// Members are visited in order, `ObjectIndex` finds them in O(1).
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<Value<'a>> {
+        let key = key.as_ref();
+        self.iter()
+            .find(|(other, _)| *other == key)
+            .map(|(_, value)| value)
+    }
+
+    // Position of the first member with `key` in the data of the object, like `ObjectIndex::find`
+    pub(crate) fn find(&self, key: &[u8]) -> Option<usize> {
+        let mut members = self.iter();
+        loop {
+            let mut pos = members.position();
+            skip_zeroes(self.data, &mut pos);
+            if members.next()?.0 == key {
+                return Some(pos);
+            }
+        }
+    }
+
+    /// Whether no key appears more than once, comparing the keys as they are stored (unescaped).
+    /// The keys are sorted in `scratch`, `None` if it has fewer slots than the object has keys.
+    pub fn has_unique_keys(&self, scratch: &mut [u32]) -> Option<bool> {
+        let pairs = scratch.get_mut(..self.length)?;
+        let mut iter = self.iter();
+        for slot in pairs.iter_mut() {
+            *slot = u32::try_from(iter.position()).ok()?;
+            iter.next()?;
+        }
+        sort_by_key(self.data, pairs);
+        Some(
+            pairs
+                .windows(2)
+                .all(|pair| key_at(self.data, pair[0]) != key_at(self.data, pair[1])),
+        )
+    }
+}
+
 impl BitmixToTLV for Object<'_> {
-    fn bitmix_to_tlv(data: &mut [u8], pos: &mut usize) -> Option<()> {
-        if data[*pos] != b'{' {
+    fn bitmix_to_tlv_with(data: &mut [u8], pos: &mut usize, ctx: &mut Context) -> Option<()> {
+        if data.get(*pos) != Some(&b'{') {
             return None;
         }
+        ctx.enter()?;
         let start = *pos;
         let mut length = 0;
         *pos += 1;
         skip_ws(data, pos);
 
         if !bitmix_consume_byte::<b'}'>(data, pos) {
-            bitmix_pair_list_and_close(data, pos, &mut length)?;
+            bitmix_pair_list_and_close(data, pos, &mut length, ctx)?;
         }
+        dedup_pairs(data, start + 1, *pos - 1, &mut length, ctx)?;
+        ctx.leave();
 
-        data[start] = 0;
-        data[*pos - 1] = 0;
-
-        Length::write(data, start, *pos, length);
-        // Bytesize::write(data, start, *pos, *pos - start - 2);
-        data[start] |= OBJECT_MASK;
-
-        Some(())
+        bitmix_close_container(data, start, *pos, length, OBJECT_MASK)
     }
 }
 
@@ -88,7 +237,7 @@ impl<'a> DecodeTLV<'a> for Object<'a> {
     type ReturnType = Self;
 
     fn decode_tlv(data: &'a [u8], pos: &mut usize) -> Option<Self::ReturnType> {
-        if *pos >= data.len() {
+        if *pos + 1 >= data.len() {
             return None;
         }
         if data[*pos] & TYPE_MASK != OBJECT_MASK {
@@ -101,29 +250,28 @@ impl<'a> DecodeTLV<'a> for Object<'a> {
         let start = *pos;
         for _ in 0..length {
             skip_zeroes(data, pos);
-
-            let at = *pos;
             if !String::skip_tlv(data, pos) {
-                panic!("invalid key at {}: {:?}", at, &data[at..]);
+                return None;
             }
 
             skip_zeroes(data, pos);
-
-            let at = *pos;
             if !Value::skip_tlv(data, pos) {
-                panic!("invalid value at {}: {:?}", at, &data[at..]);
+                return None;
             }
         }
         let end = *pos;
 
         let object = Object {
             data: &data[start..end],
+            length,
+            offset: None,
         };
         Some(object)
     }
 }
 
 #[test]
+#[allow(clippy::identity_op)] // the length bits are spelled out
 fn test_object_empty() {
     let mut pos = 1;
     let mut data = *b" {}";
@@ -168,3 +316,21 @@ fn test_object_small() {
     Object::decode_tlv(&data, &mut pos).unwrap();
     assert_eq!(pos, 17);
 }
+
+#[test]
+fn test_object_has_unique_keys() {
+    use crate::parser::Parser;
+
+    let mut data = *br#"{"a": 1, "b": {"a": 2}, "c": 3}"#;
+    let Some(Value::Object(object)) = Parser::parse(&mut data) else {
+        panic!("expected an object")
+    };
+    assert_eq!(object.has_unique_keys(&mut [0; 3]), Some(true));
+    assert_eq!(object.has_unique_keys(&mut [0; 2]), None);
+
+    let mut data = *br#"{"a": 1, "b": 2, "\u0061": 3}"#;
+    let Some(Value::Object(object)) = Parser::parse(&mut data) else {
+        panic!("expected an object")
+    };
+    assert_eq!(object.has_unique_keys(&mut [0; 8]), Some(false));
+}
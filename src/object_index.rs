@@ -0,0 +1,156 @@
+use crate::{
+    object::Object, skip_zeroes::skip_zeroes, string::String, tlv::DecodeTLV, value::Value,
+};
+
+// Open addressing hash table of the offsets of keys in the data of an object,
+// collisions are resolved by linear probing
+const EMPTY: u32 = u32::MAX;
+
+enum Slots<'s> {
+    Borrowed(&'s [u32]),
+    #[cfg(feature = "std")]
+    Owned(std::vec::Vec<u32>),
+}
+
+/// Hash table of the keys of an `Object`, see `Object::build_index`.
+pub struct ObjectIndex<'a, 's> {
+    data: &'a [u8],
+    slots: Slots<'s>,
+}
+
+// FNV-1a
+fn hash(key: &[u8]) -> usize {
+    key.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    }) as usize
+}
+
+// Slot that has `key` or the empty slot where it would go
+fn probe(data: &[u8], slots: &[u32], key: &[u8]) -> usize {
+    let mut idx = hash(key) % slots.len();
+    loop {
+        let slot = slots[idx];
+        if slot == EMPTY || String::decode_tlv(data, &mut (slot as usize)) == Some(key) {
+            return idx;
+        }
+        idx = (idx + 1) % slots.len();
+    }
+}
+
+fn fill(data: &[u8], length: usize, slots: &mut [u32]) -> Option<()> {
+    if slots.len() <= length || u32::try_from(data.len()).is_err() {
+        return None;
+    }
+    slots.fill(EMPTY);
+
+    let mut pos = 0;
+    for _ in 0..length {
+        skip_zeroes(data, &mut pos);
+        let key_pos = pos;
+        let key = String::decode_tlv(data, &mut pos)?;
+        skip_zeroes(data, &mut pos);
+        if !Value::skip_tlv(data, &mut pos) {
+            return None;
+        }
+
+        // the first member with a key wins, like in a linear scan
+        let idx = probe(data, slots, key);
+        if slots[idx] == EMPTY {
+            slots[idx] = key_pos as u32;
+        }
+    }
+    Some(())
+}
+
+impl<'a> Object<'a> {
+    /// Indexes the keys of the object in `scratch`, `None` if it doesn't have more slots
+    /// than the object has members. Lookups stay fast while at least half of the slots are free.
+    pub fn build_index<'s>(&self, scratch: &'s mut [u32]) -> Option<ObjectIndex<'a, 's>> {
+        fill(self.data, self.length, scratch)?;
+        Some(ObjectIndex {
+            data: self.data,
+            slots: Slots::Borrowed(scratch),
+        })
+    }
+
+    /// `Object::build_index` with a table of twice as many slots as members allocated for it.
+    #[cfg(feature = "std")]
+    pub fn build_owned_index(&self) -> Option<ObjectIndex<'a, 'static>> {
+        let mut slots = std::vec![EMPTY; self.length * 2 + 1];
+        fill(self.data, self.length, &mut slots)?;
+        Some(ObjectIndex {
+            data: self.data,
+            slots: Slots::Owned(slots),
+        })
+    }
+}
+
+impl<'a> ObjectIndex<'a, '_> {
+    fn slots(&self) -> &[u32] {
+        match &self.slots {
+            Slots::Borrowed(slots) => slots,
+            #[cfg(feature = "std")]
+            Slots::Owned(slots) => slots,
+        }
+    }
+
+    // Position of the first member with `key` in the data of the object
+    pub(crate) fn find(&self, key: &[u8]) -> Option<usize> {
+        let slot = self.slots()[probe(self.data, self.slots(), key)];
+        (slot != EMPTY).then_some(slot as usize)
+    }
+
+    /// Value of the first member with `key`.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<Value<'a>> {
+        let mut pos = self.find(key.as_ref())?;
+        if !String::skip_tlv(self.data, &mut pos) {
+            return None;
+        }
+        Value::decode_tlv(self.data, &mut pos)
+    }
+}
+
+#[cfg(test)]
+use crate::tests::parse_object;
+
+#[test]
+fn test_object_index() {
+    let mut data = *br#"{"a": 1, "b": [2], "a": 3, "": null, "c\u0000": "x"}"#;
+    let object = parse_object(&mut data);
+
+    assert!(object.build_index(&mut [0; 5]).is_none());
+    let mut scratch = [0; 6];
+    let index = object.build_index(&mut scratch).unwrap();
+    assert!(matches!(index.get("a"), Some(Value::Integer(1))));
+    assert!(matches!(index.get("b"), Some(Value::Array(array)) if array.len() == 1));
+    assert!(matches!(index.get(""), Some(Value::Null)));
+    assert!(matches!(index.get("c\0"), Some(Value::String(b"x"))));
+    assert!(index.get("c").is_none());
+    assert!(index.get("d").is_none());
+
+    let mut data = *b"{}";
+    let object = parse_object(&mut data);
+    assert!(object.build_index(&mut [0; 1]).unwrap().get("a").is_none());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_owned_object_index() {
+    extern crate std;
+    use std::{format, vec::Vec};
+
+    let mut data = Vec::from(&b"{"[..]);
+    for idx in 0..8000 {
+        data.extend_from_slice(format!("\"sku-{}\": {},", idx, idx).as_bytes());
+    }
+    data.extend_from_slice(b"\"last\": true}");
+    let object = parse_object(&mut data);
+
+    let index = object.build_owned_index().unwrap();
+    for idx in (0..8000).step_by(97) {
+        let key = format!("sku-{}", idx);
+        assert!(matches!(index.get(&key), Some(Value::Integer(int)) if int == idx));
+    }
+    assert!(matches!(index.get("last"), Some(Value::True)));
+    assert!(index.get("sku-8000").is_none());
+}
@@ -0,0 +1,494 @@
+use crate::{
+    length::MAX_LENGTH,
+    parser::{decode_root, ParseError, Parser},
+    span::Span,
+    tlv::BitmixToTLV,
+    validate::MAX_DEPTH,
+    value::Value,
+    ws::skip_ws,
+};
+
+/// What to do with an object that has the same key more than once,
+/// keys are compared after unescaping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    /// Every member is kept, lookups see whichever comes first.
+    #[default]
+    KeepAll,
+    /// Only the first member with a key is kept.
+    FirstWins,
+    /// Only the last member with a key is kept, at the position of the last one.
+    LastWins,
+    /// `ParseError::DuplicateKey` is returned.
+    Error,
+}
+
+/// Limits and behavior switches for `Parser::parse_with`, see `ParserOptions::builder`.
+#[derive(Debug, Clone, Copy)]
+pub struct ParserOptions {
+    pub(crate) max_document_size: usize,
+    pub(crate) max_depth: usize,
+    pub(crate) max_string_length: usize,
+    pub(crate) max_number_length: usize,
+    pub(crate) max_elements: usize,
+    pub(crate) max_keys: usize,
+    pub(crate) strict: bool,
+    pub(crate) validate_utf8: bool,
+    pub(crate) duplicate_keys: DuplicateKeys,
+}
+
+// What `Parser::parse` does: nesting is limited to `MAX_DEPTH` levels like in `Value::validate_tlv`,
+// there are no limits other than the ones of the TLV format
+pub(crate) static LENIENT: ParserOptions = ParserOptions {
+    max_document_size: usize::MAX,
+    max_depth: MAX_DEPTH,
+    max_string_length: usize::MAX,
+    max_number_length: usize::MAX,
+    max_elements: usize::MAX,
+    max_keys: usize::MAX,
+    strict: false,
+    validate_utf8: false,
+    duplicate_keys: DuplicateKeys::KeepAll,
+};
+
+impl Default for ParserOptions {
+    /// Nesting is limited to 1024 levels, only whitespace is allowed after the document
+    /// and strings must be valid UTF-8, there are no other limits.
+    fn default() -> Self {
+        Self {
+            strict: true,
+            validate_utf8: true,
+            ..LENIENT
+        }
+    }
+}
+
+impl ParserOptions {
+    pub fn builder() -> ParserOptionsBuilder {
+        ParserOptionsBuilder {
+            options: Self::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ParserOptionsBuilder {
+    options: ParserOptions,
+}
+
+impl ParserOptionsBuilder {
+    /// Maximum length of the input in bytes.
+    pub fn max_document_size(mut self, max: usize) -> Self {
+        self.options.max_document_size = max;
+        self
+    }
+
+    /// Maximum number of nested arrays and objects, at most 1024 (larger values are clamped)
+    /// because the parser recurses into them and deeper input could overflow the stack.
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.options.max_depth = max.min(MAX_DEPTH);
+        self
+    }
+
+    /// Maximum length of a string (or a key) in bytes, after unescaping.
+    pub fn max_string_length(mut self, max: usize) -> Self {
+        self.options.max_string_length = max;
+        self
+    }
+
+    /// Maximum length of a number as it's written in the input.
+    pub fn max_number_length(mut self, max: usize) -> Self {
+        self.options.max_number_length = max;
+        self
+    }
+
+    /// Maximum number of elements in an array.
+    pub fn max_elements(mut self, max: usize) -> Self {
+        self.options.max_elements = max;
+        self
+    }
+
+    /// Maximum number of keys in an object.
+    pub fn max_keys(mut self, max: usize) -> Self {
+        self.options.max_keys = max;
+        self
+    }
+
+    /// Rejects anything but whitespace after the document, like `Parser::parse` doesn't.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.strict = strict;
+        self
+    }
+
+    pub fn validate_utf8(mut self, validate: bool) -> Self {
+        self.options.validate_utf8 = validate;
+        self
+    }
+
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.options.duplicate_keys = policy;
+        self
+    }
+
+    pub fn build(self) -> ParserOptions {
+        self.options
+    }
+}
+
+// State of a bitmix pass that has to know about the options,
+// the specific error is recorded here while `None` is returned up the stack
+pub(crate) struct Context<'o> {
+    pub(crate) options: &'o ParserOptions,
+    // where objects sort their keys to find duplicates, see `Parser::parse_with_scratch`
+    pub(crate) scratch: &'o mut [u32],
+    // where `Parser::parse_with_spans` records spans, in the order the values start
+    pub(crate) spans: Option<&'o mut [Span]>,
+    pub(crate) recorded: usize,
+    pub(crate) depth: usize,
+    pub(crate) error: Option<ParseError>,
+}
+
+impl<'o> Context<'o> {
+    pub(crate) fn new(options: &'o ParserOptions, scratch: &'o mut [u32]) -> Self {
+        Self {
+            options,
+            scratch,
+            spans: None,
+            recorded: 0,
+            depth: 0,
+            error: None,
+        }
+    }
+
+    pub(crate) fn fail<T>(&mut self, error: ParseError) -> Option<T> {
+        self.error = Some(error);
+        None
+    }
+
+    pub(crate) fn enter(&mut self) -> Option<()> {
+        if self.depth >= self.options.max_depth {
+            return self.fail(ParseError::TooDeep);
+        }
+        self.depth += 1;
+        Some(())
+    }
+
+    pub(crate) fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    // Runs `bitmix` on the value or key at `pos` and records its span if there is a span table.
+    // The slot is taken before the value is bitmixed, so that containers go before their children
+    pub(crate) fn with_span(
+        &mut self,
+        data: &mut [u8],
+        pos: &mut usize,
+        bitmix: impl FnOnce(&mut [u8], &mut usize, &mut Self) -> Option<()>,
+    ) -> Option<()> {
+        let Some(spans) = &mut self.spans else {
+            return bitmix(data, pos, self);
+        };
+        let idx = self.recorded;
+        let Some(span) = spans.get_mut(idx) else {
+            return self.fail(ParseError::ScratchTooSmall);
+        };
+        span.start = *pos;
+        self.recorded += 1;
+        bitmix(data, pos, self)?;
+        if let Some(spans) = &mut self.spans {
+            spans[idx].end = *pos;
+        }
+        Some(())
+    }
+}
+
+impl Parser {
+    /// `Parser::parse` that enforces the limits of `options`
+    /// and reports which one has been hit.
+    /// Unless the policy is `DuplicateKeys::KeepAll`, the keys of objects are sorted in a table
+    /// with a slot for every key the input has room for. It's allocated with the `std` feature,
+    /// without it the table takes 8192 slots (32 KiB) of stack. `parse_with_scratch` takes
+    /// the table from the caller instead.
+    pub fn parse_with<'a>(
+        data: &'a mut [u8],
+        options: &ParserOptions,
+    ) -> Result<Value<'a>, ParseError> {
+        if options.duplicate_keys == DuplicateKeys::KeepAll {
+            return Self::parse_with_scratch(data, options, &mut []);
+        }
+        // every member takes at least 4 bytes, `"":0` and a separator
+        #[cfg(feature = "std")]
+        let mut scratch = std::vec![0; (data.len() / 4 + 1).min(MAX_LENGTH)];
+        #[cfg(not(feature = "std"))]
+        let mut scratch = [0; MAX_LENGTH];
+        Self::parse_with_scratch(data, options, &mut scratch)
+    }
+
+    /// `Parser::parse_with` that sorts the keys of objects in `scratch` to find duplicates,
+    /// it needs a slot for every key of the largest object.
+    /// `ParseError::ScratchTooSmall` is returned for an object that doesn't fit.
+    pub fn parse_with_scratch<'a>(
+        data: &'a mut [u8],
+        options: &ParserOptions,
+        scratch: &mut [u32],
+    ) -> Result<Value<'a>, ParseError> {
+        if data.len() > options.max_document_size {
+            return Err(ParseError::DocumentTooLarge);
+        }
+
+        let mut ctx = Context::new(options, scratch);
+        let mut pos = 0;
+        if Value::bitmix_to_tlv_with(data, &mut pos, &mut ctx).is_none() {
+            return Err(ctx.error.unwrap_or(ParseError::Syntax));
+        }
+        if options.strict {
+            skip_ws(data, &mut pos);
+            if pos != data.len() {
+                return Err(ParseError::TrailingCharacters);
+            }
+        }
+
+        decode_root(data).ok_or(ParseError::Syntax)
+    }
+}
+
+#[cfg(test)]
+fn parse_with(input: &[u8], options: ParserOptionsBuilder) -> Result<(), ParseError> {
+    extern crate std;
+    let mut data = std::vec::Vec::from(input);
+    Parser::parse_with(&mut data, &options.build()).map(|_| ())
+}
+
+#[test]
+fn test_limits() {
+    let options = ParserOptions::builder;
+
+    assert_eq!(
+        parse_with(b"[1, 2]", options().max_document_size(6)),
+        Ok(())
+    );
+    assert_eq!(
+        parse_with(b"[1, 2] ", options().max_document_size(6)),
+        Err(ParseError::DocumentTooLarge)
+    );
+
+    assert_eq!(parse_with(b"[[{}]]", options().max_depth(3)), Ok(()));
+    let mut deep = [b']'; 2 * MAX_DEPTH + 2];
+    deep[..MAX_DEPTH + 1].fill(b'[');
+    assert_eq!(
+        parse_with(&deep, options().max_depth(usize::MAX)),
+        Err(ParseError::TooDeep)
+    );
+    assert_eq!(
+        parse_with(b"[[{\"a\": []}]]", options().max_depth(3)),
+        Err(ParseError::TooDeep)
+    );
+
+    assert_eq!(
+        parse_with(br#"["a\"c"]"#, options().max_string_length(3)),
+        Ok(())
+    );
+    assert_eq!(
+        parse_with(br#"{"abcd": 1}"#, options().max_string_length(3)),
+        Err(ParseError::StringTooLong)
+    );
+
+    assert_eq!(
+        parse_with(b"[1.25]", options().max_number_length(4)),
+        Ok(())
+    );
+    assert_eq!(
+        parse_with(b"[1.125]", options().max_number_length(4)),
+        Err(ParseError::NumberTooLong)
+    );
+
+    assert_eq!(
+        parse_with(b"[[1, 2], 3]", options().max_elements(2)),
+        Ok(())
+    );
+    assert_eq!(
+        parse_with(b"[[1, 2, 3]]", options().max_elements(2)),
+        Err(ParseError::TooManyElements)
+    );
+
+    assert_eq!(
+        parse_with(br#"{"a": {"b": 1}}"#, options().max_keys(1)),
+        Ok(())
+    );
+    assert_eq!(
+        parse_with(br#"{"a": 1, "b": 2}"#, options().max_keys(1)),
+        Err(ParseError::TooManyKeys)
+    );
+}
+
+#[test]
+fn test_switches() {
+    let options = ParserOptions::builder;
+
+    assert_eq!(
+        parse_with(b"[1] x", options()),
+        Err(ParseError::TrailingCharacters)
+    );
+    assert_eq!(parse_with(b"[1] x", options().strict(false)), Ok(()));
+
+    assert_eq!(
+        parse_with(b"[\"\xff\"]", options()),
+        Err(ParseError::InvalidUtf8)
+    );
+    assert_eq!(
+        parse_with(b"[\"\xff\"]", options().validate_utf8(false)),
+        Ok(())
+    );
+
+    assert_eq!(parse_with(b"[1,]", options()), Err(ParseError::Syntax));
+}
+
+#[test]
+fn test_format_limits_are_errors() {
+    extern crate std;
+    use std::vec::Vec;
+
+    let mut long_string = Vec::from(&b"\""[..]);
+    long_string.resize(9000, b'a');
+    long_string.push(b'"');
+    assert_eq!(
+        parse_with(&long_string, ParserOptions::builder()),
+        Err(ParseError::StringTooLong)
+    );
+    assert!(Parser::parse(&mut long_string.clone()).is_none());
+
+    let mut long_array = Vec::from(&b"["[..]);
+    for _ in 0..9000 {
+        long_array.extend_from_slice(b"1,");
+    }
+    long_array.extend_from_slice(b"1]");
+    assert_eq!(
+        parse_with(&long_array, ParserOptions::builder()),
+        Err(ParseError::TooManyElements)
+    );
+
+    let mut deep = [b']'; 2 * MAX_DEPTH + 2];
+    deep[..MAX_DEPTH + 1].fill(b'[');
+    assert_eq!(
+        parse_with(&deep, ParserOptions::builder()),
+        Err(ParseError::TooDeep)
+    );
+    assert!(Parser::parse(&mut deep.clone()).is_none());
+    assert!(Parser::parse(&mut deep[1..2 * MAX_DEPTH + 1]).is_some());
+}
+
+#[test]
+fn test_duplicate_keys() {
+    extern crate std;
+    use crate::Value;
+    use std::vec::Vec;
+
+    fn members(input: &[u8], policy: DuplicateKeys) -> Result<Vec<(Vec<u8>, i64)>, ParseError> {
+        let mut data = Vec::from(input);
+        let options = ParserOptions::builder().duplicate_keys(policy).build();
+        let Value::Object(object) = Parser::parse_with(&mut data, &options)? else {
+            panic!("expected an object")
+        };
+        assert!(
+            policy == DuplicateKeys::KeepAll || object.has_unique_keys(&mut [0; 16]) == Some(true)
+        );
+        Ok(object
+            .iter()
+            .map(|(key, value)| {
+                let Value::Integer(n) = value else {
+                    panic!("expected an integer")
+                };
+                (Vec::from(key), n)
+            })
+            .collect())
+    }
+    let pairs = |pairs: &[(&str, i64)]| -> Vec<(Vec<u8>, i64)> {
+        pairs
+            .iter()
+            .map(|(key, n)| (Vec::from(key.as_bytes()), *n))
+            .collect()
+    };
+
+    let input = br#"{"a": 1, "b": 2, "a": 3, "\u0062": 4, "c": 5, "a": 6}"#;
+    assert_eq!(
+        members(input, DuplicateKeys::KeepAll),
+        Ok(pairs(&[
+            ("a", 1),
+            ("b", 2),
+            ("a", 3),
+            ("b", 4),
+            ("c", 5),
+            ("a", 6)
+        ]))
+    );
+    assert_eq!(
+        members(input, DuplicateKeys::FirstWins),
+        Ok(pairs(&[("a", 1), ("b", 2), ("c", 5)]))
+    );
+    assert_eq!(
+        members(input, DuplicateKeys::LastWins),
+        Ok(pairs(&[("b", 4), ("c", 5), ("a", 6)]))
+    );
+    assert_eq!(
+        members(input, DuplicateKeys::Error),
+        Err(ParseError::DuplicateKey)
+    );
+
+    assert_eq!(
+        members(br#"{"a": 1, "b": 2}"#, DuplicateKeys::Error),
+        Ok(pairs(&[("a", 1), ("b", 2)]))
+    );
+
+    // keys of different objects don't clash, nested objects are checked too
+    assert_eq!(
+        parse_with(
+            br#"{"a": {"a": 1}, "b": [{"a": 2}]}"#,
+            ParserOptions::builder().duplicate_keys(DuplicateKeys::Error)
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        parse_with(
+            br#"{"a": {"a": 1}, "b": {"b": 2, "b": 3}}"#,
+            ParserOptions::builder().duplicate_keys(DuplicateKeys::Error)
+        ),
+        Err(ParseError::DuplicateKey)
+    );
+
+    let options = ParserOptions::builder()
+        .duplicate_keys(DuplicateKeys::FirstWins)
+        .build();
+    let mut data = *br#"{"a": 1, "b": 2, "a": 3}"#;
+    assert_eq!(
+        Parser::parse_with_scratch(&mut data, &options, &mut [0; 2]).err(),
+        Some(ParseError::ScratchTooSmall)
+    );
+    let mut data = *br#"{"a": 1, "b": 2, "a": 3}"#;
+    assert!(Parser::parse_with_scratch(&mut data, &options, &mut [0; 3]).is_ok());
+
+    // every key twice with a nested value, the later ones win
+    let mut input = Vec::from(&b"{"[..]);
+    for round in 0..2 {
+        for key in 0..4000 {
+            if round + key > 0 {
+                input.push(b',');
+            }
+            input
+                .extend_from_slice(std::format!(r#""k{key}": [{round}, {{"a": [1]}}]"#).as_bytes());
+        }
+    }
+    input.push(b'}');
+    let options = ParserOptions::builder()
+        .duplicate_keys(DuplicateKeys::LastWins)
+        .build();
+    let Value::Object(object) = Parser::parse_with(&mut input, &options).unwrap() else {
+        panic!("expected an object")
+    };
+    assert_eq!(object.len(), 4000);
+    assert!(object.iter().all(|(_, value)| {
+        let Value::Array(array) = value else {
+            return false;
+        };
+        matches!(array.iter().next(), Some(Value::Integer(1)))
+    }));
+}
@@ -1,16 +1,66 @@
 use crate::{
-    tlv::{BitmixToTLV, DecodeTLV},
-    value::Value,
+    iterators::decode_at, skip_zeroes::skip_zeroes, tlv::BitmixToTLV, value::Value, ws::skip_ws,
 };
 
 pub struct Parser;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input is not a valid JSON document.
+    Syntax,
+    /// Something other than whitespace follows the document.
+    TrailingCharacters,
+    /// The input is longer than `max_document_size`.
+    DocumentTooLarge,
+    /// Arrays and objects are nested deeper than `max_depth`.
+    TooDeep,
+    /// A string or a key is longer than `max_string_length` after unescaping,
+    /// or longer than the TLV length can hold.
+    StringTooLong,
+    /// A number is longer than `max_number_length` as it's written in the input.
+    NumberTooLong,
+    /// An array has more than `max_elements` elements, or more than the TLV length can hold.
+    TooManyElements,
+    /// An object has more than `max_keys` keys, or more than the TLV length can hold.
+    TooManyKeys,
+    /// A string or a key isn't valid UTF-8 and `validate_utf8` is on.
+    InvalidUtf8,
+    /// An object has the same key more than once and the policy is `DuplicateKeys::Error`.
+    DuplicateKey,
+    /// An object has more keys than the scratch table of `Parser::parse_with_scratch` has slots,
+    /// or it's too large for the positions of its keys to fit in them. Also returned when
+    /// the document has more values and keys than the table of `Parser::parse_with_spans`.
+    ScratchTooSmall,
+}
+
 impl Parser {
+    /// Turns the JSON in `data` into TLV in place and returns its root value.
+    /// Arrays and objects can be nested up to 1024 levels, anything after the root value is ignored.
     pub fn parse(data: &mut [u8]) -> Option<Value<'_>> {
         let mut pos = 0;
         Value::bitmix_to_tlv(data, &mut pos)?;
 
-        pos = 0;
-        Value::decode_tlv(data, &mut pos)
+        decode_root(data)
+    }
+
+    // Like `parse`, but only whitespace is allowed after the root value
+    pub(crate) fn parse_document(data: &mut [u8]) -> Result<Value<'_>, ParseError> {
+        let mut pos = 0;
+        Value::bitmix_to_tlv(data, &mut pos).ok_or(ParseError::Syntax)?;
+        skip_ws(data, &mut pos);
+        if pos != data.len() {
+            return Err(ParseError::Syntax);
+        }
+
+        decode_root(data).ok_or(ParseError::Syntax)
     }
 }
+
+// Decodes the root value of a document that has just been bitmixed in place,
+// the arrays and objects in it know where they are in the input for `Value::span`
+pub(crate) fn decode_root(data: &[u8]) -> Option<Value<'_>> {
+    let mut pos = 0;
+    skip_zeroes(data, &mut pos);
+    let start = pos;
+    decode_at(data, &mut pos, Some(start))
+}
@@ -0,0 +1,520 @@
+use core::fmt::{self, Write};
+use std::{borrow::Cow, vec::Vec};
+
+use crate::{
+    pointer,
+    serialize::{write_string, write_value},
+    value::Value,
+};
+
+// Operations are applied to a tree that starts as the source value and gets expanded
+// one level at a time along the paths that the operations touch.
+// Subtrees that no operation reaches stay `Node::Source` and are written straight from TLV
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchError {
+    /// The patch is not an array.
+    InvalidPatch,
+    /// Operation at this index is not an object, has an unknown `op`, misses a member
+    /// or has a `path`/`from` that is not a JSON Pointer.
+    InvalidOperation(usize),
+    /// `path` or `from` of the operation at this index points to a location that doesn't exist.
+    PathNotFound(usize),
+    /// `test` operation at this index failed.
+    TestFailed(usize),
+    /// The writer returned an error.
+    WriteFailed,
+}
+
+#[derive(Debug, Clone)]
+enum Node<'a> {
+    Source(Value<'a>),
+    Array(Vec<Node<'a>>),
+    Object(Vec<(Cow<'a, [u8]>, Node<'a>)>),
+}
+
+// Node or a value inside of an untouched one
+enum Target<'n, 'a> {
+    Node(&'n Node<'a>),
+    Value(Value<'a>),
+}
+
+fn key_eq(key: &[u8], segment: &str) -> bool {
+    pointer::unescaped(segment).eq(key.iter().copied())
+}
+
+fn key_of(segment: &str) -> Cow<'_, [u8]> {
+    if segment.contains('~') {
+        Cow::Owned(pointer::unescaped(segment).collect())
+    } else {
+        Cow::Borrowed(segment.as_bytes())
+    }
+}
+
+fn value_child<'a>(value: Value<'a>, segment: &str) -> Option<Value<'a>> {
+    match value {
+        Value::Array(array) => array.iter().nth(pointer::index(segment)?),
+        Value::Object(object) => object
+            .iter()
+            .find(|(key, _)| key_eq(key, segment))
+            .map(|(_, value)| value),
+        _ => None,
+    }
+}
+
+impl<'a> Node<'a> {
+    fn expand(&mut self) {
+        match self {
+            Node::Source(Value::Array(array)) => {
+                *self = Node::Array(array.iter().map(Node::Source).collect());
+            }
+            Node::Source(Value::Object(object)) => {
+                *self = Node::Object(
+                    object
+                        .iter()
+                        .map(|(key, value)| (Cow::Borrowed(key), Node::Source(value)))
+                        .collect(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn child(&mut self, segment: &str) -> Option<&mut Self> {
+        self.expand();
+        match self {
+            Node::Array(nodes) => nodes.get_mut(pointer::index(segment)?),
+            Node::Object(members) => members
+                .iter_mut()
+                .find(|(key, _)| key_eq(key, segment))
+                .map(|(_, node)| node),
+            Node::Source(_) => None,
+        }
+    }
+
+    fn find(&mut self, path: &str) -> Option<&mut Self> {
+        pointer::segments(path).try_fold(self, |node, segment| node.child(segment))
+    }
+
+    // Like `find`, but doesn't expand anything
+    fn lookup(&self, path: &str) -> Option<Target<'_, 'a>> {
+        pointer::segments(path).try_fold(Target::Node(self), |target, segment| {
+            Some(match target {
+                Target::Node(Node::Source(value)) => Target::Value(value_child(*value, segment)?),
+                Target::Value(value) => Target::Value(value_child(value, segment)?),
+                Target::Node(Node::Array(nodes)) => {
+                    Target::Node(nodes.get(pointer::index(segment)?)?)
+                }
+                Target::Node(Node::Object(members)) => Target::Node(
+                    members
+                        .iter()
+                        .find(|(key, _)| key_eq(key, segment))
+                        .map(|(_, node)| node)?,
+                ),
+            })
+        })
+    }
+
+    // RFC 6902 equality: numbers by value, objects regardless of the order of members
+    fn eq_value(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Node::Source(source), value) => source.eq_ignoring_key_order(value),
+            (Node::Array(nodes), Value::Array(array)) => {
+                nodes.len() == array.len()
+                    && nodes
+                        .iter()
+                        .zip(array.iter())
+                        .all(|(node, value)| node.eq_value(&value))
+            }
+            (Node::Object(members), Value::Object(object)) => {
+                members.len() == object.len()
+                    && members.iter().all(|(key, node)| {
+                        object
+                            .iter()
+                            .find(|(other, _)| *other == &**key)
+                            .is_some_and(|(_, value)| node.eq_value(&value))
+                    })
+            }
+            _ => false,
+        }
+    }
+
+    fn write(&self, out: &mut impl Write) -> fmt::Result {
+        match self {
+            Node::Source(value) => write_value(value, None, out),
+            Node::Array(nodes) => {
+                out.write_char('[')?;
+                for (idx, node) in nodes.iter().enumerate() {
+                    if idx > 0 {
+                        out.write_char(',')?;
+                    }
+                    node.write(out)?;
+                }
+                out.write_char(']')
+            }
+            Node::Object(members) => {
+                out.write_char('{')?;
+                for (idx, (key, node)) in members.iter().enumerate() {
+                    if idx > 0 {
+                        out.write_char(',')?;
+                    }
+                    write_string(key, out)?;
+                    out.write_char(':')?;
+                    node.write(out)?;
+                }
+                out.write_char('}')
+            }
+        }
+    }
+}
+
+impl<'a> Target<'_, 'a> {
+    fn to_node(&self) -> Node<'a> {
+        match self {
+            Target::Node(node) => (*node).clone(),
+            Target::Value(value) => Node::Source(*value),
+        }
+    }
+
+    fn eq_value(&self, value: &Value) -> bool {
+        match self {
+            Target::Node(node) => node.eq_value(value),
+            Target::Value(target) => target.eq_ignoring_key_order(value),
+        }
+    }
+}
+
+fn add<'a>(root: &mut Node<'a>, path: &'a str, node: Node<'a>) -> Option<()> {
+    let Some((parent, last)) = path.rsplit_once('/') else {
+        *root = node;
+        return Some(());
+    };
+    let parent = root.find(parent)?;
+    parent.expand();
+    match parent {
+        Node::Array(nodes) => {
+            let idx = match last {
+                "-" => nodes.len(),
+                _ => pointer::index(last).filter(|idx| *idx <= nodes.len())?,
+            };
+            nodes.insert(idx, node);
+        }
+        Node::Object(members) => {
+            match members.iter().position(|(key, _)| key_eq(key, last)) {
+                // the first member is replaced and its duplicates are dropped, so that they don't resurface
+                Some(idx) => {
+                    members[idx].1 = node;
+                    let mut seen = 0;
+                    members.retain(|(key, _)| {
+                        seen += 1;
+                        seen <= idx + 1 || !key_eq(key, last)
+                    });
+                }
+                None => members.push((key_of(last), node)),
+            }
+        }
+        Node::Source(_) => return None,
+    }
+    Some(())
+}
+
+fn remove<'a>(root: &mut Node<'a>, path: &str) -> Option<Node<'a>> {
+    let (parent, last) = path.rsplit_once('/')?;
+    let parent = root.find(parent)?;
+    parent.expand();
+    match parent {
+        Node::Array(nodes) => {
+            let idx = pointer::index(last).filter(|idx| *idx < nodes.len())?;
+            Some(nodes.remove(idx))
+        }
+        Node::Object(members) => {
+            let idx = members.iter().position(|(key, _)| key_eq(key, last))?;
+            let (_, node) = members.remove(idx);
+            members.retain(|(key, _)| !key_eq(key, last));
+            Some(node)
+        }
+        Node::Source(_) => None,
+    }
+}
+
+fn pointer_of<'a>(value: Option<Value<'a>>) -> Option<&'a str> {
+    let Some(Value::String(bytes)) = value else {
+        return None;
+    };
+    core::str::from_utf8(bytes)
+        .ok()
+        .filter(|pointer| pointer::is_valid(pointer))
+}
+
+fn apply<'a>(root: &mut Node<'a>, idx: usize, operation: Value<'a>) -> Result<(), PatchError> {
+    let invalid = PatchError::InvalidOperation(idx);
+    let not_found = PatchError::PathNotFound(idx);
+    let Value::Object(operation) = operation else {
+        return Err(invalid);
+    };
+    let [op, path, from, value] = operation.extract(["op", "path", "from", "value"]);
+    let path = pointer_of(path).ok_or(invalid)?;
+
+    match op {
+        Some(Value::String(b"add")) => add(root, path, Node::Source(value.ok_or(invalid)?)),
+        Some(Value::String(b"remove")) => remove(root, path).map(drop),
+        Some(Value::String(b"replace")) => {
+            let value = value.ok_or(invalid)?;
+            root.find(path).map(|node| *node = Node::Source(value))
+        }
+        Some(Value::String(b"move")) => {
+            let from = pointer_of(from).ok_or(invalid)?;
+            // a value can't be moved into one of its own children
+            if path.len() > from.len()
+                && path.starts_with(from)
+                && path.as_bytes()[from.len()] == b'/'
+            {
+                return Err(invalid);
+            }
+            let node = remove(root, from).ok_or(not_found)?;
+            add(root, path, node)
+        }
+        Some(Value::String(b"copy")) => {
+            let from = pointer_of(from).ok_or(invalid)?;
+            let node = root.lookup(from).ok_or(not_found)?.to_node();
+            add(root, path, node)
+        }
+        Some(Value::String(b"test")) => {
+            let value = value.ok_or(invalid)?;
+            let target = root.lookup(path).ok_or(not_found)?;
+            return match target.eq_value(&value) {
+                true => Ok(()),
+                false => Err(PatchError::TestFailed(idx)),
+            };
+        }
+        _ => return Err(invalid),
+    }
+    .ok_or(not_found)
+}
+
+/// Applies an RFC 6902 JSON Patch to `source` and writes the result to `out` as compact JSON.
+///
+/// Operations are applied in order and nothing is written if any of them fails.
+/// Only containers on the paths of the operations are rebuilt, the rest is copied from `source`.
+pub fn apply_patch<'a>(
+    source: &Value<'a>,
+    patch: &Value<'a>,
+    out: &mut impl Write,
+) -> Result<(), PatchError> {
+    let Value::Array(operations) = patch else {
+        return Err(PatchError::InvalidPatch);
+    };
+    let mut root = Node::Source(*source);
+    for (idx, operation) in operations.iter().enumerate() {
+        apply(&mut root, idx, operation)?;
+    }
+    root.write(out).map_err(|_| PatchError::WriteFailed)
+}
+
+#[cfg(test)]
+fn patched(source: &str, patch: &str) -> Result<std::string::String, PatchError> {
+    use crate::parser::Parser;
+
+    let mut source = std::vec::Vec::from(source.as_bytes());
+    let mut patch = std::vec::Vec::from(patch.as_bytes());
+    let source = Parser::parse(&mut source).unwrap();
+    let patch = Parser::parse(&mut patch).unwrap();
+    let mut out = std::string::String::new();
+    apply_patch(&source, &patch, &mut out)?;
+    Ok(out)
+}
+
+#[test]
+fn test_patch_rfc_examples() {
+    let cases = [
+        (
+            r#"{"foo": "bar"}"#,
+            r#"[{"op": "add", "path": "/baz", "value": "qux"}]"#,
+            r#"{"foo":"bar","baz":"qux"}"#,
+        ),
+        (
+            r#"{"foo": ["bar", "baz"]}"#,
+            r#"[{"op": "add", "path": "/foo/1", "value": "qux"}]"#,
+            r#"{"foo":["bar","qux","baz"]}"#,
+        ),
+        (
+            r#"{"baz": "qux", "foo": "bar"}"#,
+            r#"[{"op": "remove", "path": "/baz"}]"#,
+            r#"{"foo":"bar"}"#,
+        ),
+        (
+            r#"{"foo": ["bar", "qux", "baz"]}"#,
+            r#"[{"op": "remove", "path": "/foo/1"}]"#,
+            r#"{"foo":["bar","baz"]}"#,
+        ),
+        (
+            r#"{"baz": "qux", "foo": "bar"}"#,
+            r#"[{"op": "replace", "path": "/baz", "value": "boo"}]"#,
+            r#"{"baz":"boo","foo":"bar"}"#,
+        ),
+        (
+            r#"{"foo": {"bar": "baz", "waldo": "fred"}, "qux": {"corge": "grault"}}"#,
+            r#"[{"op": "move", "from": "/foo/waldo", "path": "/qux/thud"}]"#,
+            r#"{"foo":{"bar":"baz"},"qux":{"corge":"grault","thud":"fred"}}"#,
+        ),
+        (
+            r#"{"foo": ["all", "grass", "cows", "eat"]}"#,
+            r#"[{"op": "move", "from": "/foo/1", "path": "/foo/3"}]"#,
+            r#"{"foo":["all","cows","eat","grass"]}"#,
+        ),
+        (
+            r#"{"baz": "qux", "foo": ["a", 2, "c"]}"#,
+            r#"[
+                {"op": "test", "path": "/baz", "value": "qux"},
+                {"op": "test", "path": "/foo/1", "value": 2}
+            ]"#,
+            r#"{"baz":"qux","foo":["a",2,"c"]}"#,
+        ),
+        (
+            r#"{"foo": "bar"}"#,
+            r#"[{"op": "add", "path": "/child", "value": {"grandchild": {}}}]"#,
+            r#"{"foo":"bar","child":{"grandchild":{}}}"#,
+        ),
+        (
+            r#"{"foo": "bar"}"#,
+            r#"[{"op": "add", "path": "/baz", "value": "qux", "xyz": 123}]"#,
+            r#"{"foo":"bar","baz":"qux"}"#,
+        ),
+        (
+            r#"{"/": 9, "~1": 10}"#,
+            r#"[{"op": "test", "path": "/~01", "value": 10}]"#,
+            r#"{"/":9,"~1":10}"#,
+        ),
+        (
+            r#"{"foo": ["bar"]}"#,
+            r#"[{"op": "add", "path": "/foo/-", "value": ["abc", "def"]}]"#,
+            r#"{"foo":["bar",["abc","def"]]}"#,
+        ),
+    ];
+    for (source, patch, expected) in cases {
+        assert_eq!(patched(source, patch).as_deref(), Ok(expected), "{}", patch);
+    }
+}
+
+#[test]
+fn test_patch_sequence() {
+    assert_eq!(
+        patched(
+            r#"{"a": {"b": [1, 2, {"c": 3}]}, "d": "x", "d": "y", "untouched": {"e": [1.5, null]}}"#,
+            r#"[
+                {"op": "copy", "from": "/a/b/2", "path": "/a/b/-"},
+                {"op": "add", "path": "/a/b/3/c", "value": 4},
+                {"op": "test", "path": "/a/b", "value": [1, 2.0, {"c": 3}, {"c": 4}]},
+                {"op": "remove", "path": "/a/b/0"},
+                {"op": "add", "path": "/d", "value": "z"},
+                {"op": "add", "path": "/k~1~0", "value": true},
+                {"op": "move", "from": "/a", "path": "/a"},
+                {"op": "test", "path": "/k~1~0", "value": true}
+            ]"#
+        )
+        .as_deref(),
+        Ok(r#"{"d":"z","untouched":{"e":[1.5,null]},"k/~":true,"a":{"b":[2,{"c":3},{"c":4}]}}"#)
+    );
+    assert_eq!(
+        patched(
+            r#"{"a": 1}"#,
+            r#"[{"op": "replace", "path": "", "value": [1]}, {"op": "add", "path": "/0", "value": 0}]"#
+        )
+        .as_deref(),
+        Ok("[0,1]")
+    );
+    assert_eq!(
+        patched(
+            r#"{"a": {"b": 1, "c": [1]}}"#,
+            r#"[{"op": "test", "path": "/a", "value": {"c": [1], "b": 1}}]"#
+        )
+        .as_deref(),
+        Ok(r#"{"a":{"b":1,"c":[1]}}"#)
+    );
+}
+
+#[test]
+fn test_patch_errors() {
+    let cases = [
+        (r#"{}"#, r#"{}"#, PatchError::InvalidPatch),
+        (r#"{}"#, r#"[1]"#, PatchError::InvalidOperation(0)),
+        (
+            r#"{}"#,
+            r#"[{"op": "add", "path": "/a"}]"#,
+            PatchError::InvalidOperation(0),
+        ),
+        (
+            r#"{}"#,
+            r#"[{"op": "add", "path": "a", "value": 1}]"#,
+            PatchError::InvalidOperation(0),
+        ),
+        (
+            r#"{}"#,
+            r#"[{"op": "frobnicate", "path": ""}]"#,
+            PatchError::InvalidOperation(0),
+        ),
+        (
+            r#"{}"#,
+            r#"[{"op": "move", "path": "/a"}]"#,
+            PatchError::InvalidOperation(0),
+        ),
+        (
+            r#"{"a": {}}"#,
+            r#"[{"op": "move", "from": "/a", "path": "/a/b"}]"#,
+            PatchError::InvalidOperation(0),
+        ),
+        (
+            r#"{"baz": "qux"}"#,
+            r#"[{"op": "add", "path": "/baz/bat", "value": 1}]"#,
+            PatchError::PathNotFound(0),
+        ),
+        (
+            r#"[1]"#,
+            r#"[{"op": "add", "path": "/2", "value": 1}]"#,
+            PatchError::PathNotFound(0),
+        ),
+        (
+            r#"[1]"#,
+            r#"[{"op": "add", "path": "/01", "value": 1}]"#,
+            PatchError::PathNotFound(0),
+        ),
+        (
+            r#"[1]"#,
+            r#"[{"op": "remove", "path": "/1"}]"#,
+            PatchError::PathNotFound(0),
+        ),
+        (
+            r#"{}"#,
+            r#"[{"op": "remove", "path": ""}]"#,
+            PatchError::PathNotFound(0),
+        ),
+        (
+            r#"{}"#,
+            r#"[{"op": "replace", "path": "/a", "value": 1}]"#,
+            PatchError::PathNotFound(0),
+        ),
+        (
+            r#"{}"#,
+            r#"[{"op": "copy", "from": "/a", "path": "/b"}]"#,
+            PatchError::PathNotFound(0),
+        ),
+        (
+            r#"{"a": 1}"#,
+            r#"[{"op": "remove", "path": "/a"}, {"op": "test", "path": "/a", "value": 1}]"#,
+            PatchError::PathNotFound(1),
+        ),
+        (
+            r#"{"baz": "qux"}"#,
+            r#"[{"op": "test", "path": "/baz", "value": "bar"}]"#,
+            PatchError::TestFailed(0),
+        ),
+        (
+            r#"["1"]"#,
+            r#"[{"op": "test", "path": "/0", "value": 1}]"#,
+            PatchError::TestFailed(0),
+        ),
+    ];
+    for (source, patch, expected) in cases {
+        assert_eq!(patched(source, patch), Err(expected), "{}", patch);
+    }
+}
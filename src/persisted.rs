@@ -0,0 +1,242 @@
+use crate::{
+    parser::Parser,
+    skip_zeroes::skip_zeroes,
+    tlv::{BitmixToTLV, DecodeTLV},
+    validate::TlvError,
+    value::Value,
+};
+
+//
+// Persisted TLV container, all integers are little-endian:
+//
+//   offset | size | field
+//   -------+------+---------------------------------------------
+//        0 |    4 | magic, "ZCJP"
+//        4 |    2 | format version, currently 1
+//        6 |    2 | flags, see FLAG_* below
+//        8 |    4 | payload length in bytes
+//       12 |    4 | CRC-32 (IEEE 802.3) of the payload
+//       16 |    N | payload, TLV of a single root value
+//
+// The payload is the root value exactly as it's produced by the bitmix pass,
+// including the zeroed padding between nested values, so it can be decoded
+// in place (e.g. straight from `include_bytes!` or a memory-mapped file).
+//
+// Flags describe encodings the payload relies on, a reader rejects payloads
+// with flags it doesn't know about:
+//   bit 0 - numbers use 4-bit characters with continuation-bit length chunks
+//   bit 1 - strings/containers store a 13-bit length in the first two bytes
+//
+
+const MAGIC: [u8; 4] = *b"ZCJP";
+const FORMAT_VERSION: u16 = 1;
+
+const FLAG_NUMBER_NIBBLES: u16 = 1 << 0;
+const FLAG_LENGTH_13_BITS: u16 = 1 << 1;
+const FLAGS: u16 = FLAG_NUMBER_NIBBLES | FLAG_LENGTH_13_BITS;
+
+pub const PERSISTED_HEADER_LENGTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PersistError {
+    InvalidJson,
+    BufferTooSmall,
+    PayloadTooLarge,
+    BadMagic,
+    UnsupportedVersion(u16),
+    UnsupportedFlags(u16),
+    TruncatedHeader,
+    LengthMismatch,
+    ChecksumMismatch,
+    InvalidPayload(TlvError),
+}
+
+impl Parser {
+    /// Parses JSON in `data` and writes it to `out` as a persisted TLV container,
+    /// returns the number of bytes written.
+    pub fn to_persisted(data: &mut [u8], out: &mut [u8]) -> Result<usize, PersistError> {
+        let mut pos = 0;
+        Value::bitmix_to_tlv(data, &mut pos).ok_or(PersistError::InvalidJson)?;
+
+        pos = 0;
+        skip_zeroes(data, &mut pos);
+        let start = pos;
+        if !Value::skip_tlv(data, &mut pos) {
+            return Err(PersistError::InvalidJson);
+        }
+        let payload = &data[start..pos];
+
+        let payload_length =
+            u32::try_from(payload.len()).map_err(|_| PersistError::PayloadTooLarge)?;
+        let total_length = PERSISTED_HEADER_LENGTH + payload.len();
+        if out.len() < total_length {
+            return Err(PersistError::BufferTooSmall);
+        }
+
+        out[0..4].copy_from_slice(&MAGIC);
+        out[4..6].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+        out[6..8].copy_from_slice(&FLAGS.to_le_bytes());
+        out[8..12].copy_from_slice(&payload_length.to_le_bytes());
+        out[12..16].copy_from_slice(&crc32(payload).to_le_bytes());
+        out[PERSISTED_HEADER_LENGTH..total_length].copy_from_slice(payload);
+
+        Ok(total_length)
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Reads a container written by `Parser::to_persisted`.
+    pub fn from_persisted(data: &'a [u8]) -> Result<Self, PersistError> {
+        if data.len() < PERSISTED_HEADER_LENGTH {
+            return Err(PersistError::TruncatedHeader);
+        }
+        if data[0..4] != MAGIC {
+            return Err(PersistError::BadMagic);
+        }
+        let version = u16::from_le_bytes([data[4], data[5]]);
+        if version != FORMAT_VERSION {
+            return Err(PersistError::UnsupportedVersion(version));
+        }
+        let flags = u16::from_le_bytes([data[6], data[7]]);
+        if flags != FLAGS {
+            return Err(PersistError::UnsupportedFlags(flags));
+        }
+        let payload_length = u32::from_le_bytes([data[8], data[9], data[10], data[11]]) as usize;
+        let payload = &data[PERSISTED_HEADER_LENGTH..];
+        if payload.len() != payload_length {
+            return Err(PersistError::LengthMismatch);
+        }
+        let checksum = u32::from_le_bytes([data[12], data[13], data[14], data[15]]);
+        if crc32(payload) != checksum {
+            return Err(PersistError::ChecksumMismatch);
+        }
+
+        Value::try_from_tlv(payload).map_err(PersistError::InvalidPayload)
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+const GOLDEN_JSON: &[u8] = include_bytes!("../fixtures/persisted_v1.json");
+#[cfg(test)]
+const GOLDEN_V1: &[u8] = include_bytes!("../fixtures/persisted_v1.bin");
+
+#[test]
+fn test_crc32() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+}
+
+#[test]
+fn test_to_persisted_matches_golden() {
+    let mut data = [0; 256];
+    let data = &mut data[..GOLDEN_JSON.len()];
+    data.copy_from_slice(GOLDEN_JSON);
+    let mut out = [0; 256];
+
+    let length = Parser::to_persisted(data, &mut out).unwrap();
+    assert_eq!(&out[..length], GOLDEN_V1);
+}
+
+#[test]
+fn test_from_persisted_golden() {
+    let Value::Object(object) = Value::from_persisted(GOLDEN_V1).unwrap() else {
+        panic!("expected an object");
+    };
+    let mut iter = object.iter();
+
+    let (key, value) = iter.next().unwrap();
+    assert_eq!(key, b"id");
+    assert!(matches!(value, Value::Integer(1234567890)));
+
+    let (key, value) = iter.next().unwrap();
+    assert_eq!(key, b"name");
+    assert!(matches!(value, Value::String(b"zero\ncopy")));
+
+    let (key, value) = iter.next().unwrap();
+    assert_eq!(key, b"scores");
+    let Value::Array(array) = value else {
+        panic!("expected an array");
+    };
+    let mut scores = array.iter();
+    assert!(matches!(scores.next(), Some(Value::Float(f)) if f == 2.5));
+    assert!(matches!(scores.next(), Some(Value::Float(f)) if f == -1e-3));
+    assert!(matches!(scores.next(), Some(Value::Integer(0))));
+    assert!(scores.next().is_none());
+
+    let (key, value) = iter.next().unwrap();
+    assert_eq!(key, b"nested");
+    assert!(matches!(value, Value::Object(_)));
+
+    let (key, value) = iter.next().unwrap();
+    assert_eq!(key, b"flags");
+    assert!(matches!(value, Value::Array(_)));
+
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn test_from_persisted_rejects_mismatches() {
+    let mut data = [0; 256];
+    let data = &mut data[..GOLDEN_V1.len()];
+
+    data.copy_from_slice(GOLDEN_V1);
+    data[0] = b'X';
+    assert_eq!(
+        Value::from_persisted(data).unwrap_err(),
+        PersistError::BadMagic
+    );
+
+    data.copy_from_slice(GOLDEN_V1);
+    data[4] = 2;
+    assert_eq!(
+        Value::from_persisted(data).unwrap_err(),
+        PersistError::UnsupportedVersion(2)
+    );
+
+    data.copy_from_slice(GOLDEN_V1);
+    data[6] |= 0b100;
+    assert_eq!(
+        Value::from_persisted(data).unwrap_err(),
+        PersistError::UnsupportedFlags(0b111)
+    );
+
+    data.copy_from_slice(GOLDEN_V1);
+    data[PERSISTED_HEADER_LENGTH + 4] ^= 1;
+    assert_eq!(
+        Value::from_persisted(data).unwrap_err(),
+        PersistError::ChecksumMismatch
+    );
+
+    assert_eq!(
+        Value::from_persisted(&GOLDEN_V1[..GOLDEN_V1.len() - 1]).unwrap_err(),
+        PersistError::LengthMismatch
+    );
+    for length in [0, 4, PERSISTED_HEADER_LENGTH - 1] {
+        assert_eq!(
+            Value::from_persisted(&GOLDEN_V1[..length]).unwrap_err(),
+            PersistError::TruncatedHeader
+        );
+    }
+}
+
+#[test]
+fn test_to_persisted_buffer_too_small() {
+    let mut data = *b"[1, 2, 3]";
+    let mut out = [0; PERSISTED_HEADER_LENGTH + 8];
+    assert_eq!(
+        Parser::to_persisted(&mut data, &mut out),
+        Err(PersistError::BufferTooSmall)
+    );
+}
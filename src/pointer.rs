@@ -0,0 +1,66 @@
+// JSON Pointer (RFC 6901) helpers: "" is the whole document,
+// every other pointer is a list of "/"-prefixed segments where "~1" stands for "/" and "~0" for "~"
+
+pub(crate) fn is_valid(pointer: &str) -> bool {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return false;
+    }
+    let bytes = pointer.as_bytes();
+    bytes
+        .iter()
+        .enumerate()
+        .all(|(idx, byte)| *byte != b'~' || matches!(bytes.get(idx + 1), Some(b'0' | b'1')))
+}
+
+// Segments of a valid pointer, still escaped
+pub(crate) fn segments(pointer: &str) -> impl Iterator<Item = &str> + Clone {
+    pointer.split('/').skip(1)
+}
+
+pub(crate) fn segment_count(pointer: &str) -> usize {
+    pointer.bytes().filter(|byte| *byte == b'/').count()
+}
+
+// Bytes of an escaped segment with "~0" and "~1" decoded
+pub(crate) fn unescaped(segment: &str) -> impl Iterator<Item = u8> + Clone + '_ {
+    let mut bytes = segment.bytes();
+    core::iter::from_fn(move || match bytes.next()? {
+        b'~' => match bytes.next()? {
+            b'0' => Some(b'~'),
+            _ => Some(b'/'),
+        },
+        byte => Some(byte),
+    })
+}
+
+// Array index in a segment, digits without leading zeroes
+pub(crate) fn index(segment: &str) -> Option<usize> {
+    if segment.len() > 1 && segment.starts_with('0') {
+        return None;
+    }
+    if segment.is_empty() || !segment.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    segment.parse().ok()
+}
+
+#[test]
+fn test_pointer() {
+    assert!(is_valid(""));
+    assert!(is_valid("/a~0b/c~1d/"));
+    assert!(!is_valid("a"));
+    assert!(!is_valid("/a~2"));
+    assert!(!is_valid("/a~"));
+
+    assert!(segments("").eq([""; 0]));
+    assert!(segments("/a/~1/").eq(["a", "~1", ""]));
+    assert_eq!(segment_count("/a/~1/"), 3);
+
+    assert!(unescaped("a~0~1b").eq(b"a~/b".iter().copied()));
+
+    assert_eq!(index("0"), Some(0));
+    assert_eq!(index("12"), Some(12));
+    assert_eq!(index("012"), None);
+    assert_eq!(index("-"), None);
+    assert_eq!(index("+1"), None);
+}
@@ -0,0 +1,317 @@
+use crate::{
+    lex::{self, string_end},
+    mask::{ARRAY_MASK, OBJECT_MASK},
+    parser::Parser,
+    pointer,
+    string::String,
+    tlv::{bitmix_close_container, bitmix_consume_byte, BitmixToTLV, DecodeTLV},
+    tokenizer::{Event, Tokenizer},
+    value::Value,
+    ws::skip_ws,
+};
+
+/// A set of JSON Pointer paths for `Parser::parse_projected`, a `*` segment
+/// matches any member of an object or element of an array.
+#[derive(Debug, Clone, Copy)]
+pub struct Projection<'p> {
+    paths: &'p [&'p str],
+}
+
+// Paths that are still matched at some depth are tracked in a bitmask
+type Mask = u64;
+
+impl<'p> Projection<'p> {
+    pub const MAX_PATHS: usize = Mask::BITS as usize;
+
+    /// `None` if there are more than `MAX_PATHS` paths or one of them is not a valid pointer.
+    pub fn new(paths: &'p [&'p str]) -> Option<Self> {
+        if paths.len() > Self::MAX_PATHS || !paths.iter().all(|path| pointer::is_valid(path)) {
+            return None;
+        }
+        Some(Self { paths })
+    }
+
+    fn all(&self) -> Mask {
+        let unused = (Self::MAX_PATHS - self.paths.len()) as u32;
+        Mask::MAX.checked_shr(unused).unwrap_or(0)
+    }
+
+    fn paths(&self, mask: Mask) -> impl Iterator<Item = (usize, &'p str)> + '_ {
+        (0..self.paths.len())
+            .filter(move |idx| mask & (1 << idx) != 0)
+            .map(|idx| (idx, self.paths[idx]))
+    }
+
+    // Whether one of the paths ends at this depth, i.e. the whole subtree is projected
+    fn complete(&self, mask: Mask, depth: usize) -> bool {
+        self.paths(mask)
+            .any(|(_, path)| pointer::segment_count(path) == depth)
+    }
+
+    // Paths that continue into a child, `matches` checks the segment at `depth`
+    fn child_mask(&self, mask: Mask, depth: usize, matches: impl Fn(&str) -> bool) -> Mask {
+        self.paths(mask)
+            .filter(|(_, path)| {
+                pointer::segments(path)
+                    .nth(depth)
+                    .is_some_and(|segment| segment == "*" || matches(segment))
+            })
+            .fold(0, |mask, (idx, _)| mask | 1 << idx)
+    }
+}
+
+impl Parser {
+    /// Parses only the parts of `data` that are selected by `projection`,
+    /// members and elements outside of it are validated, zeroed and left out of the result
+    /// (so indices in arrays only count projected elements).
+    /// A document that is a scalar is returned as is.
+    pub fn parse_projected<'a>(data: &'a mut [u8], projection: &Projection) -> Option<Value<'a>> {
+        let mut pos = 0;
+        skip_ws(data, &mut pos);
+        match data.get(pos)? {
+            b'{' | b'[' => {
+                bitmix_projected(data, &mut pos, projection, projection.all(), 0)?;
+            }
+            _ => Value::bitmix_to_tlv(data, &mut pos)?,
+        }
+
+        pos = 0;
+        Value::decode_tlv(data, &mut pos)
+    }
+}
+
+// Returns `false` if the value is not projected, it's validated and skipped then
+fn bitmix_projected(
+    data: &mut [u8],
+    pos: &mut usize,
+    projection: &Projection,
+    mask: Mask,
+    depth: usize,
+) -> Option<bool> {
+    if projection.complete(mask, depth) {
+        Value::bitmix_to_tlv(data, pos)?;
+        return Some(true);
+    }
+    match data.get(*pos)? {
+        b'{' => bitmix_object(data, pos, projection, mask, depth)?,
+        b'[' => bitmix_array(data, pos, projection, mask, depth)?,
+        // a path goes deeper than the document
+        _ => {
+            skip_validated(data, pos)?;
+            return Some(false);
+        }
+    }
+    Some(true)
+}
+
+fn bitmix_object(
+    data: &mut [u8],
+    pos: &mut usize,
+    projection: &Projection,
+    mask: Mask,
+    depth: usize,
+) -> Option<()> {
+    let start = *pos;
+    let mut length = 0;
+    *pos += 1;
+    skip_ws(data, pos);
+
+    if !bitmix_consume_byte::<b'}'>(data, pos) {
+        loop {
+            let member_start = *pos;
+            if data.get(*pos) != Some(&b'"') {
+                return None;
+            }
+            let key = &data[*pos + 1..string_end(data, *pos)? - 1];
+            let child = projection.child_mask(mask, depth, |segment| {
+                lex::unescaped(key).eq(pointer::unescaped(segment))
+            });
+
+            let kept = if child == 0 {
+                *pos += key.len() + 2;
+                skip_ws(data, pos);
+                if !bitmix_consume_byte::<b':'>(data, pos) {
+                    return None;
+                }
+                skip_ws(data, pos);
+                skip_validated(data, pos)?;
+                false
+            } else {
+                String::bitmix_to_tlv(data, pos)?;
+                skip_ws(data, pos);
+                if !bitmix_consume_byte::<b':'>(data, pos) {
+                    return None;
+                }
+                skip_ws(data, pos);
+                bitmix_projected(data, pos, projection, child, depth + 1)?
+            };
+            if kept {
+                length += 1;
+            } else {
+                data[member_start..*pos].fill(0);
+            }
+
+            skip_ws(data, pos);
+            if bitmix_consume_byte::<b'}'>(data, pos) {
+                break;
+            } else if bitmix_consume_byte::<b','>(data, pos) {
+                skip_ws(data, pos);
+            } else {
+                return None;
+            }
+        }
+    }
+
+    bitmix_close_container(data, start, *pos, length, OBJECT_MASK)
+}
+
+fn bitmix_array(
+    data: &mut [u8],
+    pos: &mut usize,
+    projection: &Projection,
+    mask: Mask,
+    depth: usize,
+) -> Option<()> {
+    let start = *pos;
+    let mut length = 0;
+    *pos += 1;
+    skip_ws(data, pos);
+
+    if !bitmix_consume_byte::<b']'>(data, pos) {
+        for index in 0.. {
+            let element_start = *pos;
+            let child = projection.child_mask(mask, depth, |segment| {
+                pointer::index(segment) == Some(index)
+            });
+
+            let kept = if child == 0 {
+                skip_validated(data, pos)?;
+                false
+            } else {
+                bitmix_projected(data, pos, projection, child, depth + 1)?
+            };
+            if kept {
+                length += 1;
+            } else {
+                data[element_start..*pos].fill(0);
+            }
+
+            skip_ws(data, pos);
+            if bitmix_consume_byte::<b']'>(data, pos) {
+                break;
+            } else if bitmix_consume_byte::<b','>(data, pos) {
+                skip_ws(data, pos);
+            } else {
+                return None;
+            }
+        }
+    }
+
+    bitmix_close_container(data, start, *pos, length, ARRAY_MASK)
+}
+
+// Checks the structure of a value with the tokenizer without rewriting anything
+fn skip_validated(data: &[u8], pos: &mut usize) -> Option<()> {
+    let mut tokenizer = Tokenizer::new(&data[*pos..]);
+    let mut depth = 0_usize;
+    loop {
+        match tokenizer.next()?.ok()?.event {
+            Event::StartObject | Event::StartArray => depth += 1,
+            Event::EndObject | Event::EndArray => depth -= 1,
+            Event::Key(_) => continue,
+            _ => {}
+        }
+        if depth == 0 {
+            break;
+        }
+    }
+    *pos += tokenizer.position();
+    Some(())
+}
+
+#[cfg(test)]
+fn keys<'a>(value: &Value<'a>) -> impl Iterator<Item = &'a [u8]> {
+    let Value::Object(object) = value else {
+        panic!("expected an object")
+    };
+    object.iter().map(|(key, _)| key)
+}
+
+#[test]
+fn test_projection() {
+    let mut data = *br#"{
+        "user": {"id": 42, "name": "skipped \"name\"", "roles": [1, 2]},
+        "event": {"ts": 1.5, "payload": {"deep": [true, {"x": null}]}},
+        "tags": ["a", "b"],
+        "other": "skipped"
+    }"#;
+    let paths = ["/user/id", "/event/ts", "/tags/*"];
+    let projection = Projection::new(&paths).unwrap();
+    let root = Parser::parse_projected(&mut data, &projection).unwrap();
+
+    assert!(keys(&root).eq([&b"user"[..], b"event", b"tags"]));
+    let Value::Object(object) = &root else {
+        unreachable!()
+    };
+    let mut members = object.iter();
+
+    let (_, user) = members.next().unwrap();
+    assert!(keys(&user).eq([&b"id"[..]]));
+    let (_, event) = members.next().unwrap();
+    assert!(keys(&event).eq([&b"ts"[..]]));
+    let (_, Value::Array(tags)) = members.next().unwrap() else {
+        panic!("expected an array")
+    };
+    assert_eq!(tags.len(), 2);
+
+    // skipped strings are zeroed, not unescaped
+    assert!(!data.windows(7).any(|w| w == b"skipped"));
+}
+
+#[test]
+fn test_projection_array_indices_and_escaped_keys() {
+    let mut data = *br#"[{"a/b": 1, "c~d": 2, "e": 3}, {"a/b": 4}, {"a/b": 5}]"#;
+    let paths = ["/0/a~1b", "/0/c~0d", "/2"];
+    let projection = Projection::new(&paths).unwrap();
+    let Some(Value::Array(array)) = Parser::parse_projected(&mut data, &projection) else {
+        panic!("expected an array")
+    };
+    assert_eq!(array.len(), 2);
+    let mut elements = array.iter();
+    assert!(keys(&elements.next().unwrap()).eq([&b"a/b"[..], b"c~d"]));
+    assert!(keys(&elements.next().unwrap()).eq([&b"a/b"[..]]));
+}
+
+#[test]
+fn test_projection_validates_skipped_subtrees() {
+    let paths = ["/a"];
+    let projection = Projection::new(&paths).unwrap();
+    for input in [
+        &br#"{"a": 1, "b": [1, 2,]}"#[..],
+        br#"{"a": 1, "b": {"c" 1}}"#,
+        br#"{"a": 1, "b": "\q"}"#,
+        br#"{"a": 1, "b": 01}"#,
+        br#"{"b": tru, "a": 1}"#,
+    ] {
+        let mut data = input.to_vec();
+        assert!(
+            Parser::parse_projected(&mut data, &projection).is_none(),
+            "{:?}",
+            input
+        );
+    }
+
+    assert!(Projection::new(&["a"]).is_none());
+    assert!(Projection::new(&["/a~2"]).is_none());
+}
+
+#[test]
+fn test_projection_whole_document() {
+    let mut data = *br#"{"a": [1, {"b": 2}]}"#;
+    let mut expected = data;
+    let paths = [""];
+    let projection = Projection::new(&paths).unwrap();
+    Parser::parse_projected(&mut data, &projection).unwrap();
+    Parser::parse(&mut expected).unwrap();
+    assert_eq!(data, expected);
+}
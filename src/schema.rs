@@ -0,0 +1,1163 @@
+use core::cmp::Ordering;
+use std::{
+    net::{Ipv4Addr, Ipv6Addr},
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{array::Array, diff::Path, pointer, value::Value};
+
+// JSON Schema (draft 2020-12) is compiled into a list of nodes, one per subschema,
+// where subschemas and `$ref`s are indexes of other nodes.
+// Keywords that can't be checked (like `pattern`, there are no regexes) are rejected
+// instead of being ignored, unknown keywords are annotations and are ignored
+
+/// Why `Schema::compile` failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaErrorKind {
+    /// A schema is neither an object nor a boolean.
+    InvalidSchema,
+    /// A keyword has a value that it doesn't allow.
+    InvalidKeyword,
+    /// A keyword that this validator doesn't implement.
+    Unsupported,
+    /// `$ref` doesn't point to a location in the document.
+    UnresolvedRef,
+    /// `$ref`s and in-place applicators (`allOf`, `anyOf`, `oneOf`, `not`) form a loop
+    /// that would validate the same value forever.
+    RefCycle,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    /// JSON Pointer to the schema or keyword in the document, through `$ref`s that lead to it.
+    pub path: String,
+    pub kind: SchemaErrorKind,
+}
+
+/// Keyword that rejected a value, see `ValidationError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationErrorKind<'s> {
+    /// The schema is `false`.
+    False,
+    Type,
+    Enum,
+    Const,
+    MultipleOf,
+    Minimum,
+    Maximum,
+    ExclusiveMinimum,
+    ExclusiveMaximum,
+    MinLength,
+    MaxLength,
+    MinItems,
+    MaxItems,
+    UniqueItems,
+    MinProperties,
+    MaxProperties,
+    /// A required property is missing.
+    Required(&'s [u8]),
+    Format,
+    AnyOf,
+    OneOf,
+    Not,
+}
+
+/// Error reported by `Schema::validate`.
+#[derive(Debug, Clone, Copy)]
+pub struct ValidationError<'e, 's> {
+    /// Location of the invalid value in the instance.
+    pub instance_path: &'e Path<'e>,
+    /// Location of the keyword in the schema, through `$ref`s that lead to it.
+    pub schema_path: &'e Path<'e>,
+    pub kind: ValidationErrorKind<'s>,
+}
+
+const NULL: u8 = 1 << 0;
+const BOOLEAN: u8 = 1 << 1;
+const OBJECT: u8 = 1 << 2;
+const ARRAY: u8 = 1 << 3;
+const NUMBER: u8 = 1 << 4;
+const STRING: u8 = 1 << 5;
+const INTEGER: u8 = 1 << 6;
+
+// Assertions that a schema could rely on, so ignoring them would let invalid values through
+const UNSUPPORTED: &[&[u8]] = &[
+    b"pattern",
+    b"patternProperties",
+    b"propertyNames",
+    b"contains",
+    b"minContains",
+    b"maxContains",
+    b"dependentRequired",
+    b"dependentSchemas",
+    b"if",
+    b"then",
+    b"else",
+    b"unevaluatedItems",
+    b"unevaluatedProperties",
+    b"$dynamicRef",
+    b"$dynamicAnchor",
+    b"$anchor",
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    DateTime,
+    Date,
+    Time,
+    Email,
+    Ipv4,
+    Ipv6,
+    Uuid,
+    JsonPointer,
+}
+
+#[derive(Debug)]
+enum Keyword<'s> {
+    False,
+    Type(u8),
+    Enum(Array<'s>),
+    Const(Value<'s>),
+    MultipleOf(Value<'s>),
+    Minimum(Value<'s>),
+    Maximum(Value<'s>),
+    ExclusiveMinimum(Value<'s>),
+    ExclusiveMaximum(Value<'s>),
+    MinLength(usize),
+    MaxLength(usize),
+    MinItems(usize),
+    MaxItems(usize),
+    UniqueItems,
+    MinProperties(usize),
+    MaxProperties(usize),
+    Required(Array<'s>),
+    Format(Format),
+    Properties(Vec<(&'s [u8], usize)>),
+    AdditionalProperties(usize),
+    PrefixItems(Vec<usize>),
+    Items(usize),
+    AllOf(Vec<usize>),
+    AnyOf(Vec<usize>),
+    OneOf(Vec<usize>),
+    Not(usize),
+    Ref(usize),
+}
+
+#[derive(Debug, Default)]
+struct Node<'s> {
+    keywords: Vec<Keyword<'s>>,
+}
+
+/// JSON Schema compiled from a parsed document, see `Schema::compile`.
+#[derive(Debug)]
+pub struct Schema<'s> {
+    nodes: Vec<Node<'s>>,
+}
+
+fn schema_error(path: &Path, kind: SchemaErrorKind) -> SchemaError {
+    SchemaError {
+        path: path.to_string(),
+        kind,
+    }
+}
+
+fn type_of(name: &[u8]) -> Option<u8> {
+    Some(match name {
+        b"null" => NULL,
+        b"boolean" => BOOLEAN,
+        b"object" => OBJECT,
+        b"array" => ARRAY,
+        b"number" => NUMBER,
+        b"string" => STRING,
+        b"integer" => INTEGER,
+        _ => return None,
+    })
+}
+
+fn types(value: Value) -> Option<u8> {
+    match value {
+        Value::String(name) => type_of(name),
+        Value::Array(names) => names.iter().try_fold(0, |types, name| match name {
+            Value::String(name) => Some(types | type_of(name)?),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(int) => Some(*int as f64),
+        Value::Float(float) => Some(*float),
+        _ => None,
+    }
+}
+
+fn number(value: Value) -> Option<Value> {
+    as_f64(&value).map(|_| value)
+}
+
+// Non-negative integer, 2020-12 allows integers written as floats like `1.0`
+fn count(value: Value) -> Option<usize> {
+    match value {
+        Value::Integer(int) => usize::try_from(int).ok(),
+        Value::Float(float) if float >= 0.0 && float.fract() == 0.0 => Some(float as usize),
+        _ => None,
+    }
+}
+
+fn format_of(name: &[u8]) -> Option<Format> {
+    Some(match name {
+        b"date-time" => Format::DateTime,
+        b"date" => Format::Date,
+        b"time" => Format::Time,
+        b"email" => Format::Email,
+        b"ipv4" => Format::Ipv4,
+        b"ipv6" => Format::Ipv6,
+        b"uuid" => Format::Uuid,
+        b"json-pointer" => Format::JsonPointer,
+        _ => return None,
+    })
+}
+
+// `$ref` with a JSON Pointer fragment, percent-encoding is not decoded
+fn resolve<'s>(root: Value<'s>, reference: &str) -> Option<Value<'s>> {
+    let pointer = reference.strip_prefix('#')?;
+    if !pointer::is_valid(pointer) {
+        return None;
+    }
+    pointer::segments(pointer).try_fold(root, |value, segment| match value {
+        Value::Object(object) => object
+            .iter()
+            .find(|(key, _)| pointer::unescaped(segment).eq(key.iter().copied()))
+            .map(|(_, value)| value),
+        Value::Array(array) => array.iter().nth(pointer::index(segment)?),
+        _ => None,
+    })
+}
+
+// Subschemas that apply to the same value as the schema that has them
+fn in_place<'k>(keyword: &'k Keyword) -> &'k [usize] {
+    match keyword {
+        Keyword::AllOf(nodes) | Keyword::AnyOf(nodes) | Keyword::OneOf(nodes) => nodes,
+        Keyword::Not(node) | Keyword::Ref(node) => core::slice::from_ref(node),
+        _ => &[],
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Visit {
+    New,
+    Started,
+    Done,
+}
+
+struct Compiler<'s> {
+    root: Value<'s>,
+    nodes: Vec<Node<'s>>,
+    refs: Vec<(&'s [u8], usize)>,
+}
+
+impl<'s> Compiler<'s> {
+    fn compile(&mut self, schema: Value<'s>, path: &Path) -> Result<usize, SchemaError> {
+        let idx = self.nodes.len();
+        self.nodes.push(Node::default());
+        self.nodes[idx].keywords = self.keywords(schema, path)?;
+        Ok(idx)
+    }
+
+    fn compile_list(&mut self, schemas: Value<'s>, path: &Path) -> Result<Vec<usize>, SchemaError> {
+        match schemas {
+            Value::Array(schemas) if !schemas.is_empty() => schemas
+                .iter()
+                .enumerate()
+                .map(|(idx, schema)| self.compile(schema, &path.index(idx)))
+                .collect(),
+            _ => Err(schema_error(path, SchemaErrorKind::InvalidKeyword)),
+        }
+    }
+
+    fn reference(&mut self, reference: Value<'s>, path: &Path) -> Result<usize, SchemaError> {
+        let Value::String(reference) = reference else {
+            return Err(schema_error(path, SchemaErrorKind::InvalidKeyword));
+        };
+        if let Some((_, idx)) = self.refs.iter().find(|(other, _)| *other == reference) {
+            return Ok(*idx);
+        }
+        if !reference.starts_with(b"#") {
+            return Err(schema_error(path, SchemaErrorKind::Unsupported));
+        }
+        let target = core::str::from_utf8(reference)
+            .ok()
+            .and_then(|reference| resolve(self.root, reference))
+            .ok_or_else(|| schema_error(path, SchemaErrorKind::UnresolvedRef))?;
+
+        // registered before compiling the target, so that recursive schemas find it
+        let idx = self.nodes.len();
+        self.nodes.push(Node::default());
+        self.refs.push((reference, idx));
+        self.nodes[idx].keywords = self.keywords(target, path)?;
+        Ok(idx)
+    }
+
+    fn keywords(
+        &mut self,
+        schema: Value<'s>,
+        path: &Path,
+    ) -> Result<Vec<Keyword<'s>>, SchemaError> {
+        let object = match schema {
+            Value::True => return Ok(Vec::new()),
+            Value::False => return Ok(std::vec![Keyword::False]),
+            Value::Object(object) => object,
+            _ => return Err(schema_error(path, SchemaErrorKind::InvalidSchema)),
+        };
+
+        let mut keywords = Vec::new();
+        for (key, value) in object.iter() {
+            let path = path.key(key);
+            let invalid = || schema_error(&path, SchemaErrorKind::InvalidKeyword);
+            let keyword = match key {
+                b"type" => Keyword::Type(types(value).ok_or_else(invalid)?),
+                b"enum" => match value {
+                    Value::Array(values) => Keyword::Enum(values),
+                    _ => return Err(invalid()),
+                },
+                b"const" => Keyword::Const(value),
+                b"multipleOf" => match number(value) {
+                    Some(divisor) if as_f64(&divisor).is_some_and(|divisor| divisor > 0.0) => {
+                        Keyword::MultipleOf(divisor)
+                    }
+                    _ => return Err(invalid()),
+                },
+                b"minimum" => Keyword::Minimum(number(value).ok_or_else(invalid)?),
+                b"maximum" => Keyword::Maximum(number(value).ok_or_else(invalid)?),
+                b"exclusiveMinimum" => {
+                    Keyword::ExclusiveMinimum(number(value).ok_or_else(invalid)?)
+                }
+                b"exclusiveMaximum" => {
+                    Keyword::ExclusiveMaximum(number(value).ok_or_else(invalid)?)
+                }
+                b"minLength" => Keyword::MinLength(count(value).ok_or_else(invalid)?),
+                b"maxLength" => Keyword::MaxLength(count(value).ok_or_else(invalid)?),
+                b"minItems" => Keyword::MinItems(count(value).ok_or_else(invalid)?),
+                b"maxItems" => Keyword::MaxItems(count(value).ok_or_else(invalid)?),
+                b"minProperties" => Keyword::MinProperties(count(value).ok_or_else(invalid)?),
+                b"maxProperties" => Keyword::MaxProperties(count(value).ok_or_else(invalid)?),
+                b"uniqueItems" => match value {
+                    Value::True => Keyword::UniqueItems,
+                    Value::False => continue,
+                    _ => return Err(invalid()),
+                },
+                b"required" => match value {
+                    Value::Array(names)
+                        if names.iter().all(|name| matches!(name, Value::String(_))) =>
+                    {
+                        Keyword::Required(names)
+                    }
+                    _ => return Err(invalid()),
+                },
+                b"format" => match value {
+                    // unknown formats are annotations
+                    Value::String(name) => match format_of(name) {
+                        Some(format) => Keyword::Format(format),
+                        None => continue,
+                    },
+                    _ => return Err(invalid()),
+                },
+                b"properties" => {
+                    let Value::Object(properties) = value else {
+                        return Err(invalid());
+                    };
+                    let properties = properties
+                        .iter()
+                        .map(|(name, schema)| Ok((name, self.compile(schema, &path.key(name))?)))
+                        .collect::<Result<_, _>>()?;
+                    Keyword::Properties(properties)
+                }
+                b"additionalProperties" => {
+                    Keyword::AdditionalProperties(self.compile(value, &path)?)
+                }
+                b"prefixItems" => Keyword::PrefixItems(self.compile_list(value, &path)?),
+                b"items" => Keyword::Items(self.compile(value, &path)?),
+                b"allOf" => Keyword::AllOf(self.compile_list(value, &path)?),
+                b"anyOf" => Keyword::AnyOf(self.compile_list(value, &path)?),
+                b"oneOf" => Keyword::OneOf(self.compile_list(value, &path)?),
+                b"not" => Keyword::Not(self.compile(value, &path)?),
+                b"$ref" => Keyword::Ref(self.reference(value, &path)?),
+                // compiled when a `$ref` points into them
+                b"$defs" | b"definitions" => match value {
+                    Value::Object(_) => continue,
+                    _ => return Err(invalid()),
+                },
+                _ if UNSUPPORTED.contains(&key) => {
+                    return Err(schema_error(&path, SchemaErrorKind::Unsupported))
+                }
+                _ => continue,
+            };
+            keywords.push(keyword);
+        }
+        Ok(keywords)
+    }
+
+    // Depth-first search through the subschemas that apply in place, every node is visited once:
+    // reaching a node that is still being visited closes a cycle
+    fn has_cycle(&self, node: usize, visits: &mut [Visit]) -> bool {
+        match visits[node] {
+            Visit::Started => return true,
+            Visit::Done => return false,
+            Visit::New => {}
+        }
+        visits[node] = Visit::Started;
+        let cycle = self.nodes[node]
+            .keywords
+            .iter()
+            .flat_map(in_place)
+            .any(|next| self.has_cycle(*next, visits));
+        visits[node] = Visit::Done;
+        cycle
+    }
+}
+
+fn is_date(date: &[u8]) -> bool {
+    let [y1, y2, y3, y4, b'-', m1, m2, b'-', d1, d2] = *date else {
+        return false;
+    };
+    let (Some(year), Some(month), Some(day)) = (
+        digits(&[y1, y2, y3, y4]),
+        digits(&[m1, m2]),
+        digits(&[d1, d2]),
+    ) else {
+        return false;
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+fn digits(bytes: &[u8]) -> Option<u32> {
+    bytes.iter().try_fold(0, |number, byte| {
+        byte.is_ascii_digit()
+            .then(|| number * 10 + (byte - b'0') as u32)
+    })
+}
+
+// RFC 3339 `full-time`, a leap second is allowed in any minute
+fn is_time(time: &[u8]) -> bool {
+    let [h1, h2, b':', m1, m2, b':', s1, s2, rest @ ..] = time else {
+        return false;
+    };
+    let in_range = |bytes: &[u8], max| digits(bytes).is_some_and(|number| number <= max);
+    if !in_range(&[*h1, *h2], 23) || !in_range(&[*m1, *m2], 59) || !in_range(&[*s1, *s2], 60) {
+        return false;
+    }
+    let offset = match rest {
+        [b'.', fraction @ ..] => {
+            let digits = fraction
+                .iter()
+                .take_while(|byte| byte.is_ascii_digit())
+                .count();
+            if digits == 0 {
+                return false;
+            }
+            &fraction[digits..]
+        }
+        _ => rest,
+    };
+    match offset {
+        [b'Z' | b'z'] => true,
+        [b'+' | b'-', h1, h2, b':', m1, m2] => {
+            in_range(&[*h1, *h2], 23) && in_range(&[*m1, *m2], 59)
+        }
+        _ => false,
+    }
+}
+
+fn is_uuid(uuid: &[u8]) -> bool {
+    uuid.len() == 36
+        && uuid.iter().enumerate().all(|(idx, byte)| match idx {
+            8 | 13 | 18 | 23 => *byte == b'-',
+            _ => byte.is_ascii_hexdigit(),
+        })
+}
+
+// Something before and after a single `@`, without whitespace
+fn is_email(email: &[u8]) -> bool {
+    let mut parts = email.split(|byte| *byte == b'@');
+    matches!(
+        (parts.next(), parts.next(), parts.next()),
+        (Some(local), Some(domain), None)
+            if !local.is_empty()
+                && !domain.is_empty()
+                && !email.iter().any(|byte| byte.is_ascii_whitespace())
+    )
+}
+
+fn matches_format(format: Format, string: &[u8]) -> bool {
+    let Ok(text) = core::str::from_utf8(string) else {
+        return false;
+    };
+    match format {
+        Format::DateTime => match string.iter().position(|byte| matches!(byte, b'T' | b't')) {
+            Some(idx) => is_date(&string[..idx]) && is_time(&string[idx + 1..]),
+            None => false,
+        },
+        Format::Date => is_date(string),
+        Format::Time => is_time(string),
+        Format::Email => is_email(string),
+        Format::Ipv4 => text.parse::<Ipv4Addr>().is_ok(),
+        Format::Ipv6 => text.parse::<Ipv6Addr>().is_ok(),
+        Format::Uuid => is_uuid(string),
+        Format::JsonPointer => pointer::is_valid(text),
+    }
+}
+
+fn type_bits(value: &Value) -> u8 {
+    match value {
+        Value::Null => NULL,
+        Value::True | Value::False => BOOLEAN,
+        Value::Object(_) => OBJECT,
+        Value::Array(_) => ARRAY,
+        Value::String(_) => STRING,
+        Value::Integer(_) => NUMBER | INTEGER,
+        Value::Float(float) if float.fract() == 0.0 => NUMBER | INTEGER,
+        Value::Float(_) => NUMBER,
+    }
+}
+
+// Code points, continuation bytes of UTF-8 don't start one
+fn char_count(string: &[u8]) -> usize {
+    string.iter().filter(|byte| (**byte as i8) >= -0x40).count()
+}
+
+// Decimals like 0.01 aren't exact in binary, so with a float the quotient only has to be
+// within the rounding error of the division (a few units in the last place) of an integer
+fn is_multiple(value: &Value, divisor: &Value) -> bool {
+    match (value, divisor) {
+        (Value::Integer(value), Value::Integer(divisor)) => value % divisor == 0,
+        _ => match (as_f64(value), as_f64(divisor)) {
+            (Some(value), Some(divisor)) => {
+                let quotient = value / divisor;
+                quotient.is_finite()
+                    && (quotient - quotient.round()).abs() <= quotient.abs() * 4.0 * f64::EPSILON
+            }
+            _ => true,
+        },
+    }
+}
+
+type Report<'r, 's> = &'r mut dyn FnMut(&ValidationError<'_, 's>);
+
+struct Validator<'r, 's> {
+    nodes: &'r [Node<'s>],
+    // without it the first error is enough
+    report: Option<Report<'r, 's>>,
+}
+
+impl<'s> Validator<'_, 's> {
+    fn fail(
+        &mut self,
+        instance_path: &Path,
+        schema_path: &Path,
+        kind: ValidationErrorKind<'s>,
+    ) -> bool {
+        if let Some(report) = &mut self.report {
+            report(&ValidationError {
+                instance_path,
+                schema_path,
+                kind,
+            });
+        }
+        false
+    }
+
+    // Whether `instance` matches the node, without reporting anything
+    fn matches(&self, node: usize, instance: &Value) -> bool {
+        let mut validator = Validator {
+            nodes: self.nodes,
+            report: None,
+        };
+        validator.node(node, instance, &Path::ROOT, &Path::ROOT)
+    }
+
+    fn node(
+        &mut self,
+        node: usize,
+        instance: &Value,
+        instance_path: &Path,
+        schema_path: &Path,
+    ) -> bool {
+        let nodes = self.nodes;
+        let mut valid = true;
+        for keyword in &nodes[node].keywords {
+            valid &= self.keyword(node, keyword, instance, instance_path, schema_path);
+            if !valid && self.report.is_none() {
+                return false;
+            }
+        }
+        valid
+    }
+
+    fn keyword(
+        &mut self,
+        node: usize,
+        keyword: &Keyword<'s>,
+        instance: &Value,
+        instance_path: &Path,
+        schema_path: &Path,
+    ) -> bool {
+        use ValidationErrorKind as Kind;
+
+        let (name, kind, valid): (&[u8], _, _) = match (keyword, instance) {
+            (Keyword::False, _) => return self.fail(instance_path, schema_path, Kind::False),
+            (Keyword::Type(types), _) => (b"type", Kind::Type, types & type_bits(instance) != 0),
+            (Keyword::Enum(values), _) => (
+                b"enum",
+                Kind::Enum,
+                values
+                    .iter()
+                    .any(|value| value.eq_ignoring_key_order(instance)),
+            ),
+            (Keyword::Const(value), _) => {
+                (b"const", Kind::Const, value.eq_ignoring_key_order(instance))
+            }
+            (Keyword::MultipleOf(divisor), Value::Integer(_) | Value::Float(_)) => (
+                b"multipleOf",
+                Kind::MultipleOf,
+                is_multiple(instance, divisor),
+            ),
+            (Keyword::Minimum(bound), Value::Integer(_) | Value::Float(_)) => (
+                b"minimum",
+                Kind::Minimum,
+                instance.cmp(bound) != Ordering::Less,
+            ),
+            (Keyword::Maximum(bound), Value::Integer(_) | Value::Float(_)) => (
+                b"maximum",
+                Kind::Maximum,
+                instance.cmp(bound) != Ordering::Greater,
+            ),
+            (Keyword::ExclusiveMinimum(bound), Value::Integer(_) | Value::Float(_)) => (
+                b"exclusiveMinimum",
+                Kind::ExclusiveMinimum,
+                instance.cmp(bound) == Ordering::Greater,
+            ),
+            (Keyword::ExclusiveMaximum(bound), Value::Integer(_) | Value::Float(_)) => (
+                b"exclusiveMaximum",
+                Kind::ExclusiveMaximum,
+                instance.cmp(bound) == Ordering::Less,
+            ),
+            (Keyword::MinLength(min), Value::String(string)) => {
+                (b"minLength", Kind::MinLength, char_count(string) >= *min)
+            }
+            (Keyword::MaxLength(max), Value::String(string)) => {
+                (b"maxLength", Kind::MaxLength, char_count(string) <= *max)
+            }
+            (Keyword::MinItems(min), Value::Array(array)) => {
+                (b"minItems", Kind::MinItems, array.len() >= *min)
+            }
+            (Keyword::MaxItems(max), Value::Array(array)) => {
+                (b"maxItems", Kind::MaxItems, array.len() <= *max)
+            }
+            (Keyword::UniqueItems, Value::Array(array)) => (
+                b"uniqueItems",
+                Kind::UniqueItems,
+                array.iter().enumerate().all(|(idx, value)| {
+                    !array
+                        .iter()
+                        .skip(idx + 1)
+                        .any(|other| other.eq_ignoring_key_order(&value))
+                }),
+            ),
+            (Keyword::MinProperties(min), Value::Object(object)) => {
+                (b"minProperties", Kind::MinProperties, object.len() >= *min)
+            }
+            (Keyword::MaxProperties(max), Value::Object(object)) => {
+                (b"maxProperties", Kind::MaxProperties, object.len() <= *max)
+            }
+            (Keyword::Format(format), Value::String(string)) => {
+                (b"format", Kind::Format, matches_format(*format, string))
+            }
+            (Keyword::Required(names), Value::Object(object)) => {
+                let schema_path = schema_path.key(b"required");
+                let mut valid = true;
+                for name in names.iter() {
+                    let Value::String(name) = name else {
+                        continue;
+                    };
+                    if object.get(name).is_none() {
+                        valid = self.fail(instance_path, &schema_path, Kind::Required(name));
+                        if self.report.is_none() {
+                            return false;
+                        }
+                    }
+                }
+                return valid;
+            }
+            (Keyword::AnyOf(nodes), _) => (
+                b"anyOf",
+                Kind::AnyOf,
+                nodes.iter().any(|node| self.matches(*node, instance)),
+            ),
+            (Keyword::OneOf(nodes), _) => (
+                b"oneOf",
+                Kind::OneOf,
+                nodes
+                    .iter()
+                    .filter(|node| self.matches(**node, instance))
+                    .count()
+                    == 1,
+            ),
+            (Keyword::Not(node), _) => (b"not", Kind::Not, !self.matches(*node, instance)),
+            (Keyword::AllOf(nodes), _) => {
+                let schema_path = schema_path.key(b"allOf");
+                return self.each(
+                    nodes.iter().copied().enumerate(),
+                    |validator, (idx, node)| {
+                        validator.node(node, instance, instance_path, &schema_path.index(idx))
+                    },
+                );
+            }
+            (Keyword::Ref(node), _) => {
+                return self.node(*node, instance, instance_path, &schema_path.key(b"$ref"))
+            }
+            (Keyword::Properties(properties), Value::Object(object)) => {
+                let schema_path = schema_path.key(b"properties");
+                return self.each(properties.iter(), |validator, (name, node)| {
+                    object.get(name).is_none_or(|value| {
+                        validator.node(
+                            *node,
+                            &value,
+                            &instance_path.key(name),
+                            &schema_path.key(name),
+                        )
+                    })
+                });
+            }
+            (Keyword::AdditionalProperties(additional), Value::Object(object)) => {
+                let schema_path = schema_path.key(b"additionalProperties");
+                let nodes = self.nodes;
+                let properties = nodes[node]
+                    .keywords
+                    .iter()
+                    .find_map(|keyword| match keyword {
+                        Keyword::Properties(properties) => Some(properties.as_slice()),
+                        _ => None,
+                    })
+                    .unwrap_or_default();
+                return self.each(object.iter(), |validator, (key, value)| {
+                    properties.iter().any(|(name, _)| *name == key)
+                        || validator.node(
+                            *additional,
+                            &value,
+                            &instance_path.key(key),
+                            &schema_path,
+                        )
+                });
+            }
+            (Keyword::PrefixItems(nodes), Value::Array(array)) => {
+                let schema_path = schema_path.key(b"prefixItems");
+                return self.each(
+                    nodes.iter().zip(array.iter()).enumerate(),
+                    |validator, (idx, (node, value))| {
+                        validator.node(
+                            *node,
+                            &value,
+                            &instance_path.index(idx),
+                            &schema_path.index(idx),
+                        )
+                    },
+                );
+            }
+            (Keyword::Items(items), Value::Array(array)) => {
+                let schema_path = schema_path.key(b"items");
+                let prefix = self.nodes[node]
+                    .keywords
+                    .iter()
+                    .find_map(|keyword| match keyword {
+                        Keyword::PrefixItems(nodes) => Some(nodes.len()),
+                        _ => None,
+                    })
+                    .unwrap_or_default();
+                return self.each(
+                    array.iter().enumerate().skip(prefix),
+                    |validator, (idx, value)| {
+                        validator.node(*items, &value, &instance_path.index(idx), &schema_path)
+                    },
+                );
+            }
+            // assertions only apply to values of their type
+            _ => return true,
+        };
+        valid || self.fail(instance_path, &schema_path.key(name), kind)
+    }
+
+    // Validates every item, stops at the first invalid one unless errors are reported
+    fn each<I>(
+        &mut self,
+        items: impl Iterator<Item = I>,
+        mut validate: impl FnMut(&mut Self, I) -> bool,
+    ) -> bool {
+        let mut valid = true;
+        for item in items {
+            valid &= validate(self, item);
+            if !valid && self.report.is_none() {
+                return false;
+            }
+        }
+        valid
+    }
+}
+
+impl<'s> Schema<'s> {
+    /// Compiles a JSON Schema (draft 2020-12) document.
+    ///
+    /// Supported keywords are `type`, `enum`, `const`, numeric, string, array and object bounds,
+    /// `required`, `properties`, `additionalProperties`, `prefixItems`, `items`, `uniqueItems`,
+    /// `allOf`, `anyOf`, `oneOf`, `not`, `format` (dates, times, emails, IPs, UUIDs and JSON Pointers)
+    /// and `$ref` to `#` followed by a JSON Pointer into the same document.
+    /// Other assertions, like `pattern`, are rejected.
+    pub fn compile(document: &Value<'s>) -> Result<Self, SchemaError> {
+        let mut compiler = Compiler {
+            root: *document,
+            nodes: Vec::new(),
+            refs: Vec::new(),
+        };
+        compiler.compile(*document, &Path::ROOT)?;
+        let mut visits = std::vec![Visit::New; compiler.nodes.len()];
+        if (0..compiler.nodes.len()).any(|node| compiler.has_cycle(node, &mut visits)) {
+            return Err(schema_error(&Path::ROOT, SchemaErrorKind::RefCycle));
+        }
+        Ok(Self {
+            nodes: compiler.nodes,
+        })
+    }
+
+    /// Validates `instance` and calls `on_error` for every failed assertion,
+    /// returns whether there were none.
+    ///
+    /// Failed subschemas of `anyOf`, `oneOf` and `not` are not reported one by one,
+    /// only the keyword itself is.
+    pub fn validate(
+        &self,
+        instance: &Value,
+        mut on_error: impl FnMut(&ValidationError<'_, 's>),
+    ) -> bool {
+        let mut validator = Validator {
+            nodes: &self.nodes,
+            report: Some(&mut on_error),
+        };
+        validator.node(0, instance, &Path::ROOT, &Path::ROOT)
+    }
+
+    /// `Schema::validate` that stops at the first error.
+    pub fn is_valid(&self, instance: &Value) -> bool {
+        Validator {
+            nodes: &self.nodes,
+            report: None,
+        }
+        .matches(0, instance)
+    }
+}
+
+#[cfg(test)]
+fn compiled(schema: &'static str) -> Result<Schema<'static>, SchemaError> {
+    let data = std::vec::Vec::from(schema.as_bytes()).leak();
+    Schema::compile(&crate::parser::Parser::parse(data).unwrap())
+}
+
+#[cfg(test)]
+fn errors(schema: &Schema, instance: &str) -> std::vec::Vec<String> {
+    use std::format;
+
+    let mut data = std::vec::Vec::from(instance.as_bytes());
+    let instance = crate::parser::Parser::parse(&mut data).unwrap();
+    let mut errors = std::vec::Vec::new();
+    let valid = schema.validate(&instance, |error| {
+        let kind = match error.kind {
+            ValidationErrorKind::Required(name) => {
+                format!("Required({})", String::from_utf8_lossy(name))
+            }
+            kind => format!("{:?}", kind),
+        };
+        errors.push(format!(
+            "{} {} {}",
+            error.instance_path, error.schema_path, kind
+        ));
+    });
+    assert_eq!(valid, errors.is_empty());
+    assert_eq!(schema.is_valid(&instance), valid);
+    errors
+}
+
+#[test]
+fn test_schema_validate() {
+    let schema = compiled(
+        r##"{
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "order",
+            "type": "object",
+            "required": ["id", "items", "email"],
+            "properties": {
+                "id": {"type": "integer", "minimum": 1},
+                "email": {"type": "string", "format": "email", "maxLength": 20},
+                "status": {"enum": ["new", "paid", null]},
+                "items": {
+                    "type": "array",
+                    "minItems": 1,
+                    "uniqueItems": true,
+                    "items": {"$ref": "#/$defs/item"}
+                },
+                "point": {"prefixItems": [{"type": "number"}, {"const": {"a": [1]}}], "items": false}
+            },
+            "additionalProperties": {"type": "boolean"},
+            "$defs": {
+                "item": {
+                    "type": "object",
+                    "properties": {
+                        "sku": {"type": "string", "minLength": 3},
+                        "qty": {"type": "integer", "exclusiveMinimum": 0, "multipleOf": 2}
+                    },
+                    "required": ["sku"]
+                }
+            }
+        }"##,
+    )
+    .unwrap();
+
+    assert!(errors(
+        &schema,
+        r#"{"id": 1.0, "email": "a@b.c", "status": null, "items": [{"sku": "é€x", "qty": 4}], "point": [1.5, {"a": [1.0]}], "flag": true}"#
+    )
+    .is_empty());
+    assert_eq!(
+        errors(
+            &schema,
+            r#"{"id": 0, "email": "not an email at all", "status": "lost", "items": [{"sku": "ab", "qty": 3}, {"qty": 0}, {"sku": "ab", "qty": 3}], "point": [1, {"a": [2]}, 3], "extra": 1}"#
+        ),
+        [
+            "/id /properties/id/minimum Minimum",
+            "/email /properties/email/format Format",
+            "/status /properties/status/enum Enum",
+            "/items /properties/items/uniqueItems UniqueItems",
+            "/items/0/sku /properties/items/items/$ref/properties/sku/minLength MinLength",
+            "/items/0/qty /properties/items/items/$ref/properties/qty/multipleOf MultipleOf",
+            "/items/1/qty /properties/items/items/$ref/properties/qty/exclusiveMinimum ExclusiveMinimum",
+            "/items/1 /properties/items/items/$ref/required Required(sku)",
+            "/items/2/sku /properties/items/items/$ref/properties/sku/minLength MinLength",
+            "/items/2/qty /properties/items/items/$ref/properties/qty/multipleOf MultipleOf",
+            "/point/1 /properties/point/prefixItems/1/const Const",
+            "/point/2 /properties/point/items False",
+            "/extra /additionalProperties/type Type",
+        ]
+    );
+    assert_eq!(errors(&schema, "[]"), [" /type Type"]);
+    assert_eq!(
+        errors(&schema, "{}"),
+        [
+            " /required Required(id)",
+            " /required Required(items)",
+            " /required Required(email)"
+        ]
+    );
+}
+
+#[test]
+fn test_schema_applicators() {
+    let schema = compiled(
+        r##"{
+            "allOf": [{"type": ["object", "null"]}, {"maxProperties": 2}],
+            "properties": {
+                "id": {"anyOf": [{"type": "string"}, {"type": "integer", "maximum": 10}]},
+                "kind": {"oneOf": [{"const": "a"}, {"enum": ["a", "b"]}]},
+                "name": {"not": {"const": ""}},
+                "tree": {"$ref": "#/$defs/tree"}
+            },
+            "$defs": {
+                "tree": {
+                    "type": "object",
+                    "properties": {"value": {"type": "number"}, "children": {"items": {"$ref": "#/$defs/tree"}}}
+                }
+            }
+        }"##,
+    )
+    .unwrap();
+
+    assert!(errors(&schema, "null").is_empty());
+    assert!(errors(&schema, r#"{"id": "x", "kind": "b"}"#).is_empty());
+    assert!(errors(
+        &schema,
+        r#"{"tree": {"value": 1, "children": [{"children": [{"value": 2.5}]}]}}"#
+    )
+    .is_empty());
+    assert_eq!(
+        errors(&schema, r#"{"id": 11, "kind": "a", "name": ""}"#),
+        [
+            " /allOf/1/maxProperties MaxProperties",
+            "/id /properties/id/anyOf AnyOf",
+            "/kind /properties/kind/oneOf OneOf",
+            "/name /properties/name/not Not",
+        ]
+    );
+    assert_eq!(
+        errors(&schema, r#"{"tree": {"children": [{"children": [{"value": "x"}]}]}}"#),
+        ["/tree/children/0/children/0/value /properties/tree/$ref/properties/children/items/$ref/properties/children/items/$ref/properties/value/type Type"]
+    );
+    assert_eq!(errors(&schema, "[]"), [" /allOf/0/type Type"]);
+
+    let schema = compiled("false").unwrap();
+    assert_eq!(errors(&schema, "1"), ["  False"]);
+    let schema = compiled("{\"$ref\": \"#/$defs/a\", \"$defs\": {\"a\": true}}").unwrap();
+    assert!(errors(&schema, "1").is_empty());
+}
+
+#[test]
+fn test_schema_formats() {
+    let schema = compiled(
+        r#"{"properties": {
+            "date-time": {"format": "date-time"},
+            "date": {"format": "date"},
+            "time": {"format": "time"},
+            "email": {"format": "email"},
+            "ipv4": {"format": "ipv4"},
+            "ipv6": {"format": "ipv6"},
+            "uuid": {"format": "uuid"},
+            "json-pointer": {"format": "json-pointer"},
+            "unknown": {"format": "color"}
+        }}"#,
+    )
+    .unwrap();
+    let valid = r#"{
+        "date-time": "2024-02-29T23:59:60.123+05:30",
+        "date": "2000-02-29",
+        "time": "08:30:00z",
+        "email": "user+tag@example.com",
+        "ipv4": "192.168.0.1",
+        "ipv6": "::ffff:192.168.0.1",
+        "uuid": "123e4567-E89B-12d3-a456-426614174000",
+        "json-pointer": "/a~1b/0",
+        "unknown": "red"
+    }"#;
+    assert!(errors(&schema, valid).is_empty());
+    let invalid = r#"{
+        "date-time": "2024-02-29 23:59:60Z",
+        "date": "1900-02-29",
+        "time": "24:00:00Z",
+        "email": "user@@example.com",
+        "ipv4": "192.168.0.01",
+        "ipv6": "::g",
+        "uuid": "123e4567e89b12d3a456426614174000",
+        "json-pointer": "a/b",
+        "unknown": 1
+    }"#;
+    assert_eq!(errors(&schema, invalid).len(), 8);
+    assert!(errors(&schema, r#"{"date": 20240101, "time": "12:00:00"}"#)
+        .iter()
+        .eq(["/time /properties/time/format Format"]));
+}
+
+#[test]
+fn test_schema_compile_errors() {
+    let error = |schema: &'static str| {
+        compiled(schema)
+            .map(drop)
+            .map_err(|error| std::format!("{} {:?}", error.path, error.kind))
+    };
+    assert_eq!(error("1"), Err(" InvalidSchema".into()));
+    assert_eq!(
+        error(r#"{"type": "int"}"#),
+        Err("/type InvalidKeyword".into())
+    );
+    assert_eq!(
+        error(r#"{"minLength": -1}"#),
+        Err("/minLength InvalidKeyword".into())
+    );
+    assert_eq!(
+        error(r#"{"minLength": 1.5}"#),
+        Err("/minLength InvalidKeyword".into())
+    );
+    assert_eq!(
+        error(r#"{"multipleOf": 0}"#),
+        Err("/multipleOf InvalidKeyword".into())
+    );
+    assert_eq!(
+        error(r#"{"required": [1]}"#),
+        Err("/required InvalidKeyword".into())
+    );
+    assert_eq!(
+        error(r#"{"anyOf": []}"#),
+        Err("/anyOf InvalidKeyword".into())
+    );
+    assert_eq!(
+        error(r#"{"properties": {"a/b": {"allOf": [true, 1]}}}"#),
+        Err("/properties/a~1b/allOf/1 InvalidSchema".into())
+    );
+    assert_eq!(
+        error(r#"{"items": {"pattern": "^a"}}"#),
+        Err("/items/pattern Unsupported".into())
+    );
+    assert_eq!(
+        error(r#"{"$ref": "other.json"}"#),
+        Err("/$ref Unsupported".into())
+    );
+    assert_eq!(
+        error(r##"{"$ref": "#/$defs/missing"}"##),
+        Err("/$ref UnresolvedRef".into())
+    );
+    assert_eq!(
+        error(r##"{"$ref": "#/$defs/a", "$defs": {"a": {"type": 1}}}"##),
+        Err("/$ref/type InvalidKeyword".into())
+    );
+    assert_eq!(
+        error(r##"{"anyOf": [{"$ref": "#"}]}"##),
+        Err(" RefCycle".into())
+    );
+    assert_eq!(
+        error(r##"{"items": {"$ref": "#"}, "minLength": 1.0, "x-custom": {"pattern": 1}}"##),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_schema_multiple_of() {
+    let schema = compiled(r#"{"multipleOf": 0.01}"#).unwrap();
+    for instance in ["0.3", "19.99", "1", "-4.56", "1e10", "0"] {
+        assert!(errors(&schema, instance).is_empty(), "{}", instance);
+    }
+    for instance in ["0.305", "0.001", "1.0000001"] {
+        assert_eq!(
+            errors(&schema, instance),
+            [" /multipleOf MultipleOf"],
+            "{}",
+            instance
+        );
+    }
+}
+
+#[test]
+fn test_schema_shared_refs() {
+    use std::format;
+
+    // every definition refers twice to the next one, the cycle check visits each of them once
+    // instead of following all 2^40 paths
+    let definitions: Vec<String> = (0..40)
+        .map(|idx| {
+            format!(
+                r##""d{}": {{"allOf": [{{"$ref": "#/$defs/d{}"}}, {{"$ref": "#/$defs/d{}"}}]}}"##,
+                idx,
+                idx + 1,
+                idx + 1
+            )
+        })
+        .collect();
+    let schema = format!(
+        r##"{{"$ref": "#/$defs/d0", "$defs": {{{}, "d40": {{"type": "null"}}}}}}"##,
+        definitions.join(", ")
+    );
+    assert!(compiled(std::string::String::leak(schema)).is_ok());
+}
@@ -0,0 +1,134 @@
+use crate::{
+    lex::skip_ws,
+    parser::{ParseError, Parser},
+    tlv::{BitmixToTLV, DecodeTLV},
+    value::Value,
+};
+
+// RFC 7464 record separator
+const RS: u8 = 0x1E;
+
+// Parses a value at the start of `data` and splits off the bytes it took
+fn split_prefix(data: &mut [u8]) -> Result<(Value<'_>, &mut [u8]), ParseError> {
+    let mut pos = 0;
+    Value::bitmix_to_tlv(data, &mut pos).ok_or(ParseError::Syntax)?;
+
+    let (head, tail) = data.split_at_mut(pos);
+    let head: &[u8] = head;
+    pos = 0;
+    let value = Value::decode_tlv(head, &mut pos).ok_or(ParseError::Syntax)?;
+    Ok((value, tail))
+}
+
+impl Parser {
+    /// Parses a single value from the start of `data` (after optional whitespace),
+    /// returns it together with the number of bytes it took.
+    pub fn parse_prefix(data: &mut [u8]) -> Option<(Value<'_>, usize)> {
+        let length = data.len();
+        let (value, tail) = split_prefix(data).ok()?;
+        let consumed = length - tail.len();
+        Some((value, consumed))
+    }
+
+    /// Parses a stream of concatenated JSON texts (`{"a":1}{"a":2}`) and/or
+    /// RFC 7464 sequences where every text is prefixed with a 0x1E record separator.
+    pub fn parse_sequence(data: &mut [u8]) -> Sequence<'_> {
+        Sequence {
+            record: &mut [],
+            rest: data,
+        }
+    }
+}
+
+/// Iterator over the values of a JSON text sequence, see `Parser::parse_sequence`.
+///
+/// Input is split into records by the separator first, a record that fails to parse
+/// is reported once and the iterator resumes at the next separator.
+pub struct Sequence<'a> {
+    record: &'a mut [u8],
+    rest: &'a mut [u8],
+}
+
+impl<'a> Iterator for Sequence<'a> {
+    type Item = Result<Value<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut pos = 0;
+            skip_ws(self.record, &mut pos);
+            if pos < self.record.len() {
+                let record = core::mem::take(&mut self.record);
+                return Some(match split_prefix(record) {
+                    Ok((value, tail)) => {
+                        self.record = tail;
+                        Ok(value)
+                    }
+                    // the rest of the record can't be trusted, skip to the next separator
+                    Err(err) => Err(err),
+                });
+            }
+
+            if self.rest.is_empty() {
+                return None;
+            }
+            let rest = core::mem::take(&mut self.rest);
+            let start = usize::from(rest[0] == RS);
+            let end = rest[start..]
+                .iter()
+                .position(|byte| *byte == RS)
+                .map_or(rest.len(), |end| start + end);
+            let (record, tail) = rest.split_at_mut(end);
+            self.record = &mut record[start..];
+            self.rest = tail;
+        }
+    }
+}
+
+#[test]
+fn test_parse_prefix() {
+    let mut data = *b"  [1, 2] {\"a\": 3}";
+    let (value, consumed) = Parser::parse_prefix(&mut data).unwrap();
+    assert!(matches!(value, Value::Array(array) if array.len() == 2));
+    assert_eq!(consumed, 8);
+
+    let (value, consumed) = Parser::parse_prefix(&mut data[8..]).unwrap();
+    assert!(matches!(value, Value::Object(object) if object.len() == 1));
+    assert_eq!(consumed, 9);
+
+    assert!(Parser::parse_prefix(&mut [b' '; 3]).is_none());
+}
+
+#[test]
+fn test_concatenated() {
+    let mut data = *b"{\"a\":1}{\"a\":2}\n[]\"x\"true 12 -3";
+    let mut values = Parser::parse_sequence(&mut data);
+    assert!(matches!(values.next(), Some(Ok(Value::Object(_)))));
+    assert!(matches!(values.next(), Some(Ok(Value::Object(_)))));
+    assert!(matches!(values.next(), Some(Ok(Value::Array(_)))));
+    assert!(matches!(values.next(), Some(Ok(Value::String(b"x")))));
+    assert!(matches!(values.next(), Some(Ok(Value::True))));
+    assert!(matches!(values.next(), Some(Ok(Value::Integer(12)))));
+    assert!(matches!(values.next(), Some(Ok(Value::Integer(-3)))));
+    assert!(values.next().is_none());
+}
+
+#[test]
+fn test_rs_framed_resync() {
+    let mut data = *b"\x1e{\"a\":1}\n\x1e{\"a\": [1 2]}\n\x1e\"ok\"\n\x1e[tru\n\x1e\x1e42\n";
+    let mut values = Parser::parse_sequence(&mut data);
+    assert!(matches!(values.next(), Some(Ok(Value::Object(_)))));
+    assert!(matches!(values.next(), Some(Err(ParseError::Syntax))));
+    assert!(matches!(values.next(), Some(Ok(Value::String(b"ok")))));
+    assert!(matches!(values.next(), Some(Err(ParseError::Syntax))));
+    assert!(matches!(values.next(), Some(Ok(Value::Integer(42)))));
+    assert!(values.next().is_none());
+}
+
+#[test]
+fn test_concatenated_error_without_separator_ends_stream() {
+    let mut data = *b"1 [2, } 3";
+    let mut values = Parser::parse_sequence(&mut data);
+    assert!(matches!(values.next(), Some(Ok(Value::Integer(1)))));
+    assert!(matches!(values.next(), Some(Err(ParseError::Syntax))));
+    assert!(values.next().is_none());
+}
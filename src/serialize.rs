@@ -0,0 +1,201 @@
+use core::fmt::{self, Write};
+
+use crate::{array::Array, object::Object, value::Value};
+
+// Writes JSON, `{}` gives compact output and `{:#}` indents it with 2 spaces.
+// Invalid UTF-8 in strings is replaced with U+FFFD, and floats that JSON can't express with `null`
+
+pub(crate) fn write_string(bytes: &[u8], out: &mut impl Write) -> fmt::Result {
+    out.write_char('"')?;
+    write_escaped(bytes, out)?;
+    out.write_char('"')
+}
+
+/// Contents of a JSON string with `bytes`, without the quotes.
+pub(crate) fn write_escaped(bytes: &[u8], out: &mut impl Write) -> fmt::Result {
+    for chunk in bytes.utf8_chunks() {
+        let valid = chunk.valid();
+        let mut plain = 0;
+        for (idx, char) in valid.char_indices() {
+            let escape = match char {
+                '"' => "\\\"",
+                '\\' => "\\\\",
+                '\n' => "\\n",
+                '\r' => "\\r",
+                '\t' => "\\t",
+                '\u{8}' => "\\b",
+                '\u{c}' => "\\f",
+                '\0'..='\u{1f}' => "",
+                _ => continue,
+            };
+            out.write_str(&valid[plain..idx])?;
+            plain = idx + 1;
+            if escape.is_empty() {
+                write!(out, "\\u{:04x}", char as u32)?;
+            } else {
+                out.write_str(escape)?;
+            }
+        }
+        out.write_str(&valid[plain..])?;
+        if !chunk.invalid().is_empty() {
+            out.write_char(char::REPLACEMENT_CHARACTER)?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn write_float(float: f64, out: &mut impl Write) -> fmt::Result {
+    if float.is_finite() {
+        // `Debug` keeps the fraction of integral floats and switches to exponents for large ones
+        write!(out, "{:?}", float)
+    } else {
+        out.write_str("null")
+    }
+}
+
+fn write_indent(indent: Option<usize>, out: &mut impl Write) -> fmt::Result {
+    if let Some(indent) = indent {
+        out.write_char('\n')?;
+        for _ in 0..indent {
+            out.write_str("  ")?;
+        }
+    }
+    Ok(())
+}
+
+fn write_array(array: &Array, indent: Option<usize>, out: &mut impl Write) -> fmt::Result {
+    out.write_char('[')?;
+    let inner = indent.map(|indent| indent + 1);
+    for (idx, value) in array.iter().enumerate() {
+        if idx > 0 {
+            out.write_char(',')?;
+        }
+        write_indent(inner, out)?;
+        write_value(&value, inner, out)?;
+    }
+    if !array.is_empty() {
+        write_indent(indent, out)?;
+    }
+    out.write_char(']')
+}
+
+fn write_object(object: &Object, indent: Option<usize>, out: &mut impl Write) -> fmt::Result {
+    out.write_char('{')?;
+    let inner = indent.map(|indent| indent + 1);
+    for (idx, (key, value)) in object.iter().enumerate() {
+        if idx > 0 {
+            out.write_char(',')?;
+        }
+        write_indent(inner, out)?;
+        write_string(key, out)?;
+        out.write_str(if indent.is_some() { ": " } else { ":" })?;
+        write_value(&value, inner, out)?;
+    }
+    if !object.is_empty() {
+        write_indent(indent, out)?;
+    }
+    out.write_char('}')
+}
+
+/// Writes `value` as JSON, indented with 2 spaces per level if `indent` is the current level.
+pub(crate) fn write_value(
+    value: &Value,
+    indent: Option<usize>,
+    out: &mut impl Write,
+) -> fmt::Result {
+    match value {
+        Value::Object(object) => write_object(object, indent, out),
+        Value::Array(array) => write_array(array, indent, out),
+        Value::String(string) => write_string(string, out),
+        Value::Integer(int) => write!(out, "{}", int),
+        Value::Float(float) => write_float(*float, out),
+        Value::True => out.write_str("true"),
+        Value::False => out.write_str("false"),
+        Value::Null => out.write_str("null"),
+    }
+}
+
+fn indent(f: &fmt::Formatter) -> Option<usize> {
+    f.alternate().then_some(0)
+}
+
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(self, indent(f), f)
+    }
+}
+
+impl fmt::Display for Array<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_array(self, indent(f), f)
+    }
+}
+
+impl fmt::Display for Object<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_object(self, indent(f), f)
+    }
+}
+
+#[cfg(test)]
+extern crate std;
+
+#[cfg(test)]
+fn to_json(value: &Value, pretty: bool) -> std::string::String {
+    if pretty {
+        std::format!("{:#}", value)
+    } else {
+        std::format!("{}", value)
+    }
+}
+
+#[test]
+fn test_display() {
+    use crate::parser::Parser;
+
+    let mut data = *br#" { "a" : [ 1 , -2.5e-3 , 10.0 ] , "b\"\u0001\n" : { } , "c" : [ ] , "d" : [ true , false , null ] } "#;
+    let value = Parser::parse(&mut data).unwrap();
+    assert_eq!(
+        to_json(&value, false),
+        r#"{"a":[1,-0.0025,10.0],"b\"\u0001\n":{},"c":[],"d":[true,false,null]}"#
+    );
+    assert_eq!(
+        to_json(&value, true),
+        r#"{
+  "a": [
+    1,
+    -0.0025,
+    10.0
+  ],
+  "b\"\u0001\n": {},
+  "c": [],
+  "d": [
+    true,
+    false,
+    null
+  ]
+}"#
+    );
+
+    // output parses back to an equal value
+    let mut json = to_json(&value, false).into_bytes();
+    assert_eq!(Parser::parse(&mut json).unwrap(), value);
+}
+
+#[test]
+fn test_display_strings() {
+    assert_eq!(
+        to_json(
+            &Value::String("caf\u{e9} \u{7f}\t\u{8}\u{c}\\/".as_bytes()),
+            false
+        ),
+        "\"caf\u{e9} \u{7f}\\t\\b\\f\\\\/\""
+    );
+    assert_eq!(
+        to_json(&Value::String(b"a\xffb\xe2\x82"), false),
+        "\"a\u{fffd}b\u{fffd}\""
+    );
+    assert_eq!(to_json(&Value::Float(f64::NAN), false), "null");
+    assert_eq!(to_json(&Value::Float(f64::INFINITY), false), "null");
+    assert_eq!(to_json(&Value::Float(1e21), false), "1e21");
+}
@@ -0,0 +1,278 @@
+// Byte classification used by the scanning loops of the bitmix phase.
+//
+// Every function returns the length of the run of bytes of some class starting at `pos`:
+//   1. `ws_run` - JSON whitespace
+//   2. `string_run` - string bytes that can be copied as is (anything but '"', '\' and control characters)
+//   3. `number_run` - characters that can appear in a number
+//
+// On x86_64 16 (SSE2) or 32 (AVX2, detected once at runtime with `std`) bytes are classified at once,
+// the tail that doesn't fill a whole block is handled by the scalar implementation.
+// The first 16 bytes of a run are always checked one by one, most runs between tokens are short
+// and don't get to the vector code. Structural characters are matched by the parsers byte by byte,
+// only these runs are vectorized.
+
+pub(crate) mod scalar {
+    use crate::lex::is_ws;
+
+    fn is_number_char(byte: u8) -> bool {
+        matches!(byte, b'-' | b'+' | b'0'..=b'9' | b'.' | b'e' | b'E')
+    }
+
+    fn is_plain_string_byte(byte: u8) -> bool {
+        !matches!(byte, b'"' | b'\\' | 0..=0x1F)
+    }
+
+    fn run(data: &[u8], pos: usize, f: impl Fn(u8) -> bool) -> usize {
+        data[pos.min(data.len())..]
+            .iter()
+            .take_while(|byte| f(**byte))
+            .count()
+    }
+
+    pub(crate) fn ws_run(data: &[u8], pos: usize) -> usize {
+        run(data, pos, is_ws)
+    }
+
+    pub(crate) fn string_run(data: &[u8], pos: usize) -> usize {
+        run(data, pos, is_plain_string_byte)
+    }
+
+    pub(crate) fn number_run(data: &[u8], pos: usize) -> usize {
+        run(data, pos, is_number_char)
+    }
+}
+
+// Processes whole blocks of `$width` bytes while `$stop` (a bitmask of bytes that end the run)
+// is empty, then lets the scalar implementation finish the tail
+#[cfg(target_arch = "x86_64")]
+macro_rules! block_scan {
+    ($data:ident, $pos:ident, $width:literal, $scalar:path, |$ptr:ident| $stop:expr) => {{
+        let mut idx = $pos;
+        while idx + $width <= $data.len() {
+            let $ptr = $data.as_ptr().add(idx);
+            let stop: u32 = $stop;
+            if stop != 0 {
+                return idx + stop.trailing_zeros() as usize - $pos;
+            }
+            idx += $width;
+        }
+        idx - $pos + $scalar($data, idx)
+    }};
+}
+
+#[cfg(target_arch = "x86_64")]
+pub(crate) mod sse2 {
+    use super::scalar;
+    use core::arch::x86_64::*;
+
+    const ALL: u32 = 0xFFFF;
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn eq(v: __m128i, byte: u8) -> __m128i {
+        _mm_cmpeq_epi8(v, _mm_set1_epi8(byte as i8))
+    }
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn load(ptr: *const u8) -> __m128i {
+        _mm_loadu_si128(ptr as *const __m128i)
+    }
+
+    // SAFETY: the caller must make sure that SSE2 is available
+    #[target_feature(enable = "sse2")]
+    pub(crate) unsafe fn ws_run(data: &[u8], pos: usize) -> usize {
+        block_scan!(data, pos, 16, scalar::ws_run, |ptr| {
+            let v = load(ptr);
+            let ws = _mm_or_si128(
+                _mm_or_si128(eq(v, b' '), eq(v, b'\t')),
+                _mm_or_si128(eq(v, b'\n'), eq(v, b'\r')),
+            );
+            !(_mm_movemask_epi8(ws) as u32) & ALL
+        })
+    }
+
+    // SAFETY: the caller must make sure that SSE2 is available
+    #[target_feature(enable = "sse2")]
+    pub(crate) unsafe fn string_run(data: &[u8], pos: usize) -> usize {
+        block_scan!(data, pos, 16, scalar::string_run, |ptr| {
+            let v = load(ptr);
+            // max(v, 0x1F) == 0x1F only for bytes below 0x20
+            let control = eq(_mm_max_epu8(v, _mm_set1_epi8(0x1F)), 0x1F);
+            let special = _mm_or_si128(_mm_or_si128(eq(v, b'"'), eq(v, b'\\')), control);
+            _mm_movemask_epi8(special) as u32
+        })
+    }
+
+    // SAFETY: the caller must make sure that SSE2 is available
+    #[target_feature(enable = "sse2")]
+    pub(crate) unsafe fn number_run(data: &[u8], pos: usize) -> usize {
+        block_scan!(data, pos, 16, scalar::number_run, |ptr| {
+            let v = load(ptr);
+            let digit = _mm_and_si128(
+                _mm_cmpeq_epi8(_mm_max_epu8(v, _mm_set1_epi8(b'0' as i8)), v),
+                _mm_cmpeq_epi8(_mm_min_epu8(v, _mm_set1_epi8(b'9' as i8)), v),
+            );
+            let sign = _mm_or_si128(eq(v, b'-'), eq(v, b'+'));
+            let other = _mm_or_si128(eq(v, b'.'), _mm_or_si128(eq(v, b'e'), eq(v, b'E')));
+            let number = _mm_or_si128(digit, _mm_or_si128(sign, other));
+            !(_mm_movemask_epi8(number) as u32) & ALL
+        })
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub(crate) mod avx2 {
+    use super::scalar;
+    use core::arch::x86_64::*;
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn eq(v: __m256i, byte: u8) -> __m256i {
+        _mm256_cmpeq_epi8(v, _mm256_set1_epi8(byte as i8))
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn load(ptr: *const u8) -> __m256i {
+        _mm256_loadu_si256(ptr as *const __m256i)
+    }
+
+    // SAFETY: the caller must make sure that AVX2 is available
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn ws_run(data: &[u8], pos: usize) -> usize {
+        block_scan!(data, pos, 32, scalar::ws_run, |ptr| {
+            let v = load(ptr);
+            let ws = _mm256_or_si256(
+                _mm256_or_si256(eq(v, b' '), eq(v, b'\t')),
+                _mm256_or_si256(eq(v, b'\n'), eq(v, b'\r')),
+            );
+            !(_mm256_movemask_epi8(ws) as u32)
+        })
+    }
+
+    // SAFETY: the caller must make sure that AVX2 is available
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn string_run(data: &[u8], pos: usize) -> usize {
+        block_scan!(data, pos, 32, scalar::string_run, |ptr| {
+            let v = load(ptr);
+            let control = eq(_mm256_max_epu8(v, _mm256_set1_epi8(0x1F)), 0x1F);
+            let special = _mm256_or_si256(_mm256_or_si256(eq(v, b'"'), eq(v, b'\\')), control);
+            _mm256_movemask_epi8(special) as u32
+        })
+    }
+
+    // SAFETY: the caller must make sure that AVX2 is available
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn number_run(data: &[u8], pos: usize) -> usize {
+        block_scan!(data, pos, 32, scalar::number_run, |ptr| {
+            let v = load(ptr);
+            let digit = _mm256_and_si256(
+                _mm256_cmpeq_epi8(_mm256_max_epu8(v, _mm256_set1_epi8(b'0' as i8)), v),
+                _mm256_cmpeq_epi8(_mm256_min_epu8(v, _mm256_set1_epi8(b'9' as i8)), v),
+            );
+            let sign = _mm256_or_si256(eq(v, b'-'), eq(v, b'+'));
+            let other = _mm256_or_si256(eq(v, b'.'), _mm256_or_si256(eq(v, b'e'), eq(v, b'E')));
+            let number = _mm256_or_si256(digit, _mm256_or_si256(sign, other));
+            !(_mm256_movemask_epi8(number) as u32)
+        })
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", feature = "std"))]
+fn has_avx2() -> bool {
+    extern crate std;
+    use core::sync::atomic::{AtomicU8, Ordering};
+
+    // 0 until the CPU has been asked, then 1 without AVX2 and 2 with it
+    static DETECTED: AtomicU8 = AtomicU8::new(0);
+    match DETECTED.load(Ordering::Relaxed) {
+        0 => {
+            let avx2 = std::is_x86_feature_detected!("avx2");
+            DETECTED.store(1 + avx2 as u8, Ordering::Relaxed);
+            avx2
+        }
+        detected => detected == 2,
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+fn has_avx2() -> bool {
+    cfg!(target_feature = "avx2")
+}
+
+macro_rules! dispatch {
+    ($name:ident) => {
+        #[cfg(target_arch = "x86_64")]
+        pub(crate) fn $name(data: &[u8], pos: usize) -> usize {
+            let head = scalar::$name(&data[..data.len().min(pos + 16)], pos);
+            if head < 16 {
+                return head;
+            }
+            // SAFETY: AVX2 is detected first, SSE2 is a part of the x86_64 baseline
+            head + if has_avx2() {
+                unsafe { avx2::$name(data, pos + 16) }
+            } else {
+                unsafe { sse2::$name(data, pos + 16) }
+            }
+        }
+
+        #[cfg(not(target_arch = "x86_64"))]
+        pub(crate) use scalar::$name;
+    };
+}
+
+dispatch!(ws_run);
+dispatch!(string_run);
+dispatch!(number_run);
+
+#[cfg(all(test, target_arch = "x86_64"))]
+fn assert_same_runs(data: &[u8]) {
+    for pos in 0..=data.len() + 1 {
+        let ws = scalar::ws_run(data, pos);
+        let string = scalar::string_run(data, pos);
+        let number = scalar::number_run(data, pos);
+        assert_eq!(ws_run(data, pos), ws, "{:?} at {}", data, pos);
+        assert_eq!(string_run(data, pos), string, "{:?} at {}", data, pos);
+        assert_eq!(number_run(data, pos), number, "{:?} at {}", data, pos);
+
+        unsafe {
+            assert_eq!(sse2::ws_run(data, pos), ws, "{:?} at {}", data, pos);
+            assert_eq!(sse2::string_run(data, pos), string, "{:?} at {}", data, pos);
+            assert_eq!(sse2::number_run(data, pos), number, "{:?} at {}", data, pos);
+        }
+        if has_avx2() {
+            unsafe {
+                assert_eq!(avx2::ws_run(data, pos), ws, "{:?} at {}", data, pos);
+                assert_eq!(avx2::string_run(data, pos), string, "{:?} at {}", data, pos);
+                assert_eq!(avx2::number_run(data, pos), number, "{:?} at {}", data, pos);
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[test]
+fn test_simd_matches_scalar_on_json() {
+    assert_same_runs(b"");
+    assert_same_runs(b"        \t\t\r\n                                 \t x");
+    assert_same_runs(b"-12345678901234567890.123456789e+1234567890123456789E-5,");
+    assert_same_runs(
+        br#""a long string without anything special in it, but then \" and \\ and a quote" "#,
+    );
+    assert_same_runs(b"\"tab\tinside a string that is long enough for two blocks\x01\"");
+    assert_same_runs(
+        "\"caf\u{e9} \u{1f600} non-ascii bytes are copied as is, \u{7f}\u{ff}\"".as_bytes(),
+    );
+}
+
+#[cfg(target_arch = "x86_64")]
+#[test]
+fn test_simd_matches_scalar_on_every_byte() {
+    // every byte value at every position of a block, surrounded by runs of each class
+    for fill in [b' ', b'a', b'7'] {
+        for byte in 0..=u8::MAX {
+            for at in [0, 5, 15, 16, 31, 32, 40] {
+                let mut data = [fill; 70];
+                data[at] = byte;
+                assert_same_runs(&data);
+            }
+        }
+    }
+}
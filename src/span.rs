@@ -0,0 +1,186 @@
+use crate::{
+    options::{Context, LENIENT},
+    parser::{decode_root, ParseError, Parser},
+    tlv::BitmixToTLV,
+    value::Value,
+};
+
+/// Byte range of a value or a key in the input of `Parser::parse_with_spans`.
+///
+/// TLV keeps values where they were (shifted by one byte per enclosing container),
+/// but not where they end: escaped strings shrink and closing brackets are overwritten,
+/// so the ends are recorded while parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Spans of the values and keys of a document in the order they start,
+/// see `Parser::parse_with_spans`.
+#[derive(Debug, Clone, Copy)]
+pub struct Spans<'s> {
+    spans: &'s [Span],
+}
+
+impl<'s> Spans<'s> {
+    pub fn as_slice(&self) -> &'s [Span] {
+        self.spans
+    }
+
+    // Span of the value or key that starts at `start`, the starts are sorted
+    pub(crate) fn at(&self, start: usize) -> Option<Span> {
+        let idx = self
+            .spans
+            .binary_search_by_key(&start, |span| span.start)
+            .ok()?;
+        Some(self.spans[idx])
+    }
+}
+
+impl Parser {
+    /// `Parser::parse` that records the span of every value and key in `spans`, so that
+    /// `Value::span` and the iterators can look them up. A slot per byte of `data`
+    /// is always enough, `ParseError::ScratchTooSmall` is returned if they don't fit.
+    pub fn parse_with_spans<'a, 's>(
+        data: &'a mut [u8],
+        spans: &'s mut [Span],
+    ) -> Result<(Value<'a>, Spans<'s>), ParseError> {
+        let mut ctx = Context::new(&LENIENT, &mut []);
+        ctx.spans = Some(&mut *spans);
+        let mut pos = 0;
+        if Value::bitmix_to_tlv_with(data, &mut pos, &mut ctx).is_none() {
+            return Err(ctx.error.unwrap_or(ParseError::Syntax));
+        }
+        let recorded = ctx.recorded;
+
+        let root = decode_root(data).ok_or(ParseError::Syntax)?;
+        let spans = Spans {
+            spans: &spans[..recorded],
+        };
+        Ok((root, spans))
+    }
+}
+
+impl Span {
+    /// 1-based line and column of `offset` in `source`, the column is counted in bytes.
+    pub fn line_column(source: &[u8], offset: usize) -> (usize, usize) {
+        let before = &source[..offset.min(source.len())];
+        let line = before.iter().filter(|byte| **byte == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |idx| idx + 1);
+        (line, before.len() - line_start + 1)
+    }
+}
+
+#[test]
+fn test_spans() {
+    let source = br#"{
+  "name": "a\"b",
+  "ports": [80, [true, {"x": null}], -1.5e3],
+  "nested": {"deep": [[]]}
+}"#;
+    let mut data = *source;
+    let mut table = [Span::default(); 17];
+    let (root, spans) = Parser::parse_with_spans(&mut data, &mut table).unwrap();
+    assert_eq!(spans.as_slice().len(), 17);
+    let text = |span: Option<Span>| {
+        let span = span.unwrap();
+        &source[span.start..span.end]
+    };
+
+    assert_eq!(text(root.span(&spans)), &source[..]);
+    let Value::Object(object) = &root else {
+        panic!("expected an object")
+    };
+    let mut members = object.iter();
+
+    members.next().unwrap();
+    assert_eq!(text(members.key_span(&spans)), br#""name""#);
+    assert_eq!(text(members.span(&spans)), br#""a\"b""#);
+
+    let (_, ports) = members.next().unwrap();
+    assert_eq!(
+        text(ports.span(&spans)),
+        br#"[80, [true, {"x": null}], -1.5e3]"#
+    );
+    let Value::Array(ports) = ports else {
+        panic!("expected an array")
+    };
+    let mut elements = ports.iter();
+    elements.next().unwrap();
+    assert_eq!(text(elements.span(&spans)), b"80");
+    let inner = elements.next().unwrap();
+    assert_eq!(text(inner.span(&spans)), br#"[true, {"x": null}]"#);
+    elements.next().unwrap();
+    assert_eq!(text(elements.span(&spans)), b"-1.5e3");
+    assert_eq!(
+        Span::line_column(source, elements.span(&spans).unwrap().start),
+        (3, 38)
+    );
+
+    let Value::Array(inner) = inner else {
+        panic!("expected an array")
+    };
+    let mut inner = inner.iter();
+    inner.next().unwrap();
+    assert_eq!(text(inner.span(&spans)), b"true");
+    let Value::Object(x) = inner.next().unwrap() else {
+        panic!("expected an object")
+    };
+    let mut x = x.iter();
+    x.next().unwrap();
+    assert_eq!(text(x.span(&spans)), b"null");
+    assert_eq!(
+        Span::line_column(source, x.span(&spans).unwrap().start),
+        (3, 30)
+    );
+
+    let (_, nested) = members.next().unwrap();
+    assert_eq!(text(nested.span(&spans)), br#"{"deep": [[]]}"#);
+    assert_eq!(
+        Span::line_column(source, nested.span(&spans).unwrap().start),
+        (4, 13)
+    );
+
+    let mut data = *source;
+    assert_eq!(
+        Parser::parse_with_spans(&mut data, &mut [Span::default(); 16]).err(),
+        Some(ParseError::ScratchTooSmall)
+    );
+}
+
+#[test]
+fn test_spans_of_decoded_tlv() {
+    let source = br#" [1, {"a": [2]}]"#;
+    let mut data = *source;
+    let mut table = [Span::default(); 16];
+    let (root, spans) = Parser::parse_with_spans(&mut data, &mut table).unwrap();
+    assert_eq!(root.span(&spans), Some(Span { start: 1, end: 16 }));
+
+    // the same TLV decoded on its own can't be traced back to the input
+    let decoded = Value::from_tlv(&data).unwrap();
+    assert_eq!(decoded.span(&spans), None);
+    let Value::Array(array) = decoded else {
+        panic!("expected an array")
+    };
+    let mut elements = array.iter();
+    elements.next().unwrap();
+    assert_eq!(elements.span(&spans), None);
+    assert_eq!(elements.next().unwrap().span(&spans), None);
+
+    let compacted = Parser::compact(&mut data);
+    assert_eq!(Value::from_tlv(compacted).unwrap().span(&spans), None);
+}
+
+#[test]
+fn test_line_column() {
+    let source = b"ab\ncd\n\nef";
+    assert_eq!(Span::line_column(source, 0), (1, 1));
+    assert_eq!(Span::line_column(source, 2), (1, 3));
+    assert_eq!(Span::line_column(source, 3), (2, 1));
+    assert_eq!(Span::line_column(source, 6), (3, 1));
+    assert_eq!(Span::line_column(source, 8), (4, 2));
+}
@@ -0,0 +1,52 @@
+// Fixed-capacity stack for iterative tree walks that can't allocate
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Stack<T: Copy + Default, const N: usize> {
+    items: [T; N],
+    len: usize,
+}
+
+impl<T: Copy + Default, const N: usize> Stack<T, N> {
+    pub(crate) fn new() -> Self {
+        Self {
+            items: [T::default(); N],
+            len: 0,
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn push(&mut self, item: T) -> Option<()> {
+        *self.items.get_mut(self.len)? = item;
+        self.len += 1;
+        Some(())
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        self.len = self.len.checked_sub(1)?;
+        Some(self.items[self.len])
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub(crate) fn last_mut(&mut self) -> Option<&mut T> {
+        self.items[..self.len].last_mut()
+    }
+}
+
+#[test]
+fn test_stack() {
+    let mut stack = Stack::<u8, 2>::new();
+    assert_eq!(stack.pop(), None);
+    assert_eq!(stack.push(1), Some(()));
+    assert_eq!(stack.push(2), Some(()));
+    assert_eq!(stack.push(3), None);
+    *stack.last_mut().unwrap() = 4;
+    assert_eq!(stack.pop(), Some(4));
+    assert_eq!(stack.pop(), Some(1));
+    assert_eq!(stack.pop(), None);
+}
@@ -0,0 +1,463 @@
+use crate::{
+    length::{Length, MAX_LENGTH},
+    lex::{escape, number_length, Escape},
+    mask::{ARRAY_MASK, OBJECT_MASK, STRING_MASK},
+    number::Number,
+    parser::ParseError,
+    simd::string_run,
+    stack::Stack,
+    tlv::{bitmix_close_container, BitmixToTLV, DecodeTLV},
+    true_false_null::TrueFalseNull,
+    validate::MAX_DEPTH,
+    value::Value,
+    ws::skip_ws,
+};
+
+// Longest escape sequence, a surrogate pair \uXXXX\uXXXX
+const MAX_ESCAPE_LENGTH: usize = 12;
+
+#[derive(Debug)]
+pub enum Status<'a> {
+    /// The document isn't complete yet, call `feed` again once more bytes arrive.
+    NeedMoreData,
+    Complete(Value<'a>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    Value,
+    ValueOrEnd,
+    Key,
+    KeyOrEnd,
+    Colon,
+    CommaOrEnd,
+    Done,
+    Failed,
+}
+
+// Token that has been started but not finished in one of the previous calls
+#[derive(Debug, Clone, Copy)]
+enum Partial {
+    None,
+    String {
+        start: usize,
+        read: usize,
+        write: usize,
+        key: bool,
+    },
+    Number {
+        start: usize,
+    },
+    Literal {
+        start: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Frame {
+    start: usize,
+    length: usize,
+    is_object: bool,
+}
+
+enum Step {
+    Done,
+    NeedMoreData,
+}
+
+/// Resumable version of `Parser::parse` for input that arrives in chunks.
+///
+/// Every call to `feed` gets the whole buffer received so far: the bytes passed
+/// previously (already rewritten in place) followed by the new ones. Parsing continues
+/// from where the previous call stopped, the only bytes that are looked at again
+/// are the ones of a token that was cut off by the end of the previous chunk
+/// when it gets encoded.
+pub struct StreamingParser {
+    pos: usize,
+    expect: Expect,
+    partial: Partial,
+    containers: Stack<Frame, MAX_DEPTH>,
+}
+
+impl Default for StreamingParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamingParser {
+    pub fn new() -> Self {
+        Self {
+            pos: 0,
+            expect: Expect::Value,
+            partial: Partial::None,
+            containers: Stack::new(),
+        }
+    }
+
+    // Makes the parser ready for the next document without re-initializing the stack
+    pub(crate) fn reset(&mut self) {
+        self.pos = 0;
+        self.expect = Expect::Value;
+        self.partial = Partial::None;
+        self.containers.clear();
+    }
+
+    /// Number of bytes of the buffer that have been processed so far.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn feed<'a>(&mut self, data: &'a mut [u8]) -> Result<Status<'a>, ParseError> {
+        match self.run(data, false)? {
+            Step::Done => Ok(Status::Complete(decode(data)?)),
+            Step::NeedMoreData => Ok(Status::NeedMoreData),
+        }
+    }
+
+    /// Tells the parser that no more data is coming. Required to complete
+    /// documents that are a bare number, since only the end of input terminates them.
+    pub fn finish<'a>(&mut self, data: &'a mut [u8]) -> Result<Value<'a>, ParseError> {
+        match self.run(data, true)? {
+            Step::Done => decode(data),
+            Step::NeedMoreData => {
+                self.expect = Expect::Failed;
+                Err(ParseError::Syntax)
+            }
+        }
+    }
+
+    fn run(&mut self, data: &mut [u8], eof: bool) -> Result<Step, ParseError> {
+        if self.expect == Expect::Failed {
+            return Err(ParseError::Syntax);
+        }
+        let step = self.step(data, eof);
+        if step.is_none() {
+            self.expect = Expect::Failed;
+        }
+        step.ok_or(ParseError::Syntax)
+    }
+
+    fn step(&mut self, data: &mut [u8], eof: bool) -> Option<Step> {
+        loop {
+            match self.partial {
+                Partial::None => {}
+                Partial::String { .. } => {
+                    if !self.continue_string(data)? {
+                        return Some(Step::NeedMoreData);
+                    }
+                    continue;
+                }
+                Partial::Number { .. } => {
+                    if !self.continue_number(data, eof)? {
+                        return Some(Step::NeedMoreData);
+                    }
+                    continue;
+                }
+                Partial::Literal { .. } => {
+                    if !self.continue_literal(data)? {
+                        return Some(Step::NeedMoreData);
+                    }
+                    continue;
+                }
+            }
+
+            if self.expect == Expect::Done {
+                return Some(Step::Done);
+            }
+
+            skip_ws(data, &mut self.pos);
+            let Some(&byte) = data.get(self.pos) else {
+                return Some(Step::NeedMoreData);
+            };
+
+            match (self.expect, byte) {
+                (Expect::Value | Expect::ValueOrEnd, _) if byte != b']' => {
+                    self.start_value(data)?
+                }
+                (Expect::ValueOrEnd | Expect::CommaOrEnd, b']') => self.close(data, false)?,
+                (Expect::KeyOrEnd | Expect::CommaOrEnd, b'}') => self.close(data, true)?,
+                (Expect::Key | Expect::KeyOrEnd, b'"') => self.start_string(true),
+                (Expect::Colon, b':') => {
+                    data[self.pos] = 0;
+                    self.pos += 1;
+                    self.expect = Expect::Value;
+                }
+                (Expect::CommaOrEnd, b',') => {
+                    data[self.pos] = 0;
+                    self.pos += 1;
+                    self.expect = match self.containers.last_mut() {
+                        Some(frame) if frame.is_object => Expect::Key,
+                        _ => Expect::Value,
+                    };
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn start_value(&mut self, data: &mut [u8]) -> Option<()> {
+        match data[self.pos] {
+            b'{' | b'[' => {
+                let is_object = data[self.pos] == b'{';
+                self.containers.push(Frame {
+                    start: self.pos,
+                    length: 0,
+                    is_object,
+                })?;
+                self.pos += 1;
+                self.expect = if is_object {
+                    Expect::KeyOrEnd
+                } else {
+                    Expect::ValueOrEnd
+                };
+            }
+            b'"' => self.start_string(false),
+            b'-' | b'0'..=b'9' => self.partial = Partial::Number { start: self.pos },
+            b't' | b'f' | b'n' => self.partial = Partial::Literal { start: self.pos },
+            _ => return None,
+        }
+        Some(())
+    }
+
+    fn value_done(&mut self) {
+        self.expect = match self.containers.last_mut() {
+            Some(frame) => {
+                frame.length += 1;
+                Expect::CommaOrEnd
+            }
+            None => Expect::Done,
+        };
+    }
+
+    fn close(&mut self, data: &mut [u8], is_object: bool) -> Option<()> {
+        let frame = self.containers.pop()?;
+        if frame.is_object != is_object {
+            return None;
+        }
+        self.pos += 1;
+
+        let mask = if is_object { OBJECT_MASK } else { ARRAY_MASK };
+        bitmix_close_container(data, frame.start, self.pos, frame.length, mask)?;
+
+        self.value_done();
+        Some(())
+    }
+
+    fn start_string(&mut self, key: bool) {
+        self.partial = Partial::String {
+            start: self.pos,
+            read: self.pos + 1,
+            write: self.pos + 1,
+            key,
+        };
+    }
+
+    // Unescapes the string in place as far as the data goes,
+    // returns `false` if the closing quote hasn't arrived yet
+    fn continue_string(&mut self, data: &mut [u8]) -> Option<bool> {
+        let Partial::String {
+            start,
+            mut read,
+            mut write,
+            key,
+        } = self.partial
+        else {
+            unreachable!()
+        };
+
+        while let Some(&byte) = data.get(read) {
+            match byte {
+                b'\\' => match escape(data, read) {
+                    Some((Escape::Byte(byte), length)) => {
+                        data[write] = byte;
+                        write += 1;
+                        read += length;
+                    }
+                    Some((Escape::Char(char), length)) => {
+                        char.encode_utf8(&mut data[write..]);
+                        write += char.len_utf8();
+                        read += length;
+                    }
+                    // the escape may be cut off by the end of the chunk
+                    None if data.len() - read < MAX_ESCAPE_LENGTH => break,
+                    None => return None,
+                },
+                b'"' => {
+                    let written = write + 1 - start;
+                    if written - 2 >= MAX_LENGTH {
+                        return None;
+                    }
+                    data[write] = b'"';
+                    data[write + 1..=read].iter_mut().for_each(|byte| *byte = 0);
+                    Length::write(data, start, start + written, written - 2);
+                    data[start] |= STRING_MASK;
+
+                    self.pos = read + 1;
+                    self.partial = Partial::None;
+                    if key {
+                        self.expect = Expect::Colon;
+                    } else {
+                        self.value_done();
+                    }
+                    return Some(true);
+                }
+                // control characters must be escaped
+                0..=0x1F => return None,
+                _ => {
+                    let run = string_run(data, read);
+                    data.copy_within(read..read + run, write);
+                    write += run;
+                    read += run;
+                }
+            }
+        }
+
+        self.pos = read;
+        self.partial = Partial::String {
+            start,
+            read,
+            write,
+            key,
+        };
+        Some(false)
+    }
+
+    // A number only ends at a non-number byte (or at the end of input),
+    // so it's encoded once the end is known
+    fn continue_number(&mut self, data: &mut [u8], eof: bool) -> Option<bool> {
+        let Partial::Number { mut start } = self.partial else {
+            unreachable!()
+        };
+
+        self.pos += number_length(data, self.pos);
+        if self.pos == data.len() && !eof {
+            return Some(false);
+        }
+
+        Number::bitmix_to_tlv(data, &mut start)?;
+        self.partial = Partial::None;
+        self.value_done();
+        Some(true)
+    }
+
+    fn continue_literal(&mut self, data: &mut [u8]) -> Option<bool> {
+        let Partial::Literal { mut start } = self.partial else {
+            unreachable!()
+        };
+        let literal: &[u8] = match data[start] {
+            b't' => b"true",
+            b'f' => b"false",
+            _ => b"null",
+        };
+
+        if data.len() < start + literal.len() {
+            if !literal.starts_with(&data[start..]) {
+                return None;
+            }
+            self.pos = data.len();
+            return Some(false);
+        }
+
+        TrueFalseNull::bitmix_to_tlv(data, &mut start)?;
+        self.pos = start;
+        self.partial = Partial::None;
+        self.value_done();
+        Some(true)
+    }
+}
+
+fn decode(data: &[u8]) -> Result<Value<'_>, ParseError> {
+    let mut pos = 0;
+    Value::decode_tlv(data, &mut pos).ok_or(ParseError::Syntax)
+}
+
+#[cfg(test)]
+extern crate std;
+
+#[cfg(test)]
+// Every call sees one more byte of `buffer`
+fn feed_bytewise(buffer: &mut [u8]) -> Option<usize> {
+    let mut parser = StreamingParser::new();
+    for idx in 0..buffer.len() {
+        match parser.feed(&mut buffer[..=idx]) {
+            Ok(Status::Complete(_)) => return Some(idx + 1),
+            Ok(Status::NeedMoreData) => {}
+            Err(_) => return None,
+        }
+    }
+    parser.finish(buffer).ok()?;
+    Some(buffer.len())
+}
+
+#[test]
+fn test_streaming_matches_parser() {
+    use crate::Parser;
+
+    for input in [
+        &br#" {"a": [1, -2.5e3, "x\"y\u00e9\ud83d\ude00"], "b": {}, "c": [true, false, null], "d": ""} "#[..],
+        b"[[[]], {\"a\": {\"b\": []}}]",
+        b"\"\\\\\\n\"",
+        b"1234567890123 ",
+        b"null",
+    ] {
+        let mut expected = input.to_vec();
+        Parser::parse(&mut expected).unwrap();
+
+        let mut buffer = input.to_vec();
+        assert!(feed_bytewise(&mut buffer).is_some(), "{:?}", input);
+        assert_eq!(buffer, expected, "{:?}", input);
+    }
+}
+
+#[test]
+fn test_streaming_chunks() {
+    let input = br#"{"key": "value", "list": [1, 2, 3]}"#;
+    let mut buffer = *input;
+    let mut parser = StreamingParser::new();
+
+    assert!(matches!(
+        parser.feed(&mut buffer[..10]),
+        Ok(Status::NeedMoreData)
+    ));
+    assert_eq!(parser.position(), 10);
+    assert!(matches!(
+        parser.feed(&mut buffer[..27]),
+        Ok(Status::NeedMoreData)
+    ));
+    let Ok(Status::Complete(Value::Object(object))) = parser.feed(&mut buffer) else {
+        panic!("expected a complete object")
+    };
+    assert_eq!(object.len(), 2);
+}
+
+#[test]
+fn test_streaming_root_number_needs_finish() {
+    let mut buffer = *b"42";
+    let mut parser = StreamingParser::new();
+    assert!(matches!(parser.feed(&mut buffer), Ok(Status::NeedMoreData)));
+    assert!(matches!(parser.finish(&mut buffer), Ok(Value::Integer(42))));
+}
+
+#[test]
+fn test_streaming_errors() {
+    for input in [
+        &b"[1 2]"[..],
+        b"[1,]",
+        b"{\"a\" 1}",
+        b"{1: 2}",
+        b"[}",
+        b"[tru]",
+        b"\"\\x123456789012\"",
+        b"\"a\nb\"",
+        b"[01]",
+        b"[",
+        b"\"abc",
+    ] {
+        assert_eq!(feed_bytewise(&mut input.to_vec()), None, "{:?}", input);
+    }
+
+    let mut parser = StreamingParser::new();
+    assert!(parser.feed(&mut [b']']).is_err());
+    assert!(parser.feed(&mut [b'[', b']']).is_err());
+}
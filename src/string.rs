@@ -1,20 +1,15 @@
 use crate::{
-    length::Length,
+    length::{Length, MAX_LENGTH},
+    lex::{escape, Escape},
     mask::{STRING_MASK, TYPE_MASK},
+    options::Context,
+    parser::ParseError,
+    simd::string_run,
     tlv::{BitmixToTLV, DecodeTLV},
 };
 
 pub(crate) struct String;
 
-fn unhex(c: u8) -> u8 {
-    match c {
-        b'0'..=b'9' => c - b'0',
-        b'a'..=b'f' => c - b'a' + 10,
-        b'A'..=b'F' => c - b'A' + 10,
-        _ => panic!("invalid escape sequence"),
-    }
-}
-
 struct UnescapingResult {
     read_bytesize: usize,
     written_bytesize: usize,
@@ -32,50 +27,32 @@ fn rewrite_unescaped_json_string(data: &mut [u8], pos: usize) -> Option<Unescapi
         }
 
         if data[read_from] == b'\\' {
-            match data[read_from + 1] {
-                b'n' => {
-                    data[write_to] = b'\n';
-                    write_to += 1;
-                    read_from += 2;
-                }
-                b't' => {
-                    data[write_to] = b'\t';
-                    write_to += 1;
-                    read_from += 2;
-                }
-                b'\\' => {
-                    data[write_to] = b'\\';
+            let (escape, escape_length) = escape(data, read_from)?;
+            match escape {
+                Escape::Byte(byte) => {
+                    data[write_to] = byte;
                     write_to += 1;
-                    read_from += 2;
                 }
-                b'u' => {
-                    let byte1 = unhex(data[read_from + 2]);
-                    let byte2 = unhex(data[read_from + 3]);
-                    let byte3 = unhex(data[read_from + 4]);
-                    let byte4 = unhex(data[read_from + 5]);
-                    let as_u32 = (byte1 as u32) << 12
-                        | (byte2 as u32) << 8
-                        | (byte3 as u32) << 4
-                        | byte4 as u32;
-                    let as_char = char::from_u32(as_u32).unwrap();
-                    as_char.encode_utf8(&mut data[write_to..]);
-                    write_to += as_char.len_utf8();
-                    read_from += 6;
+                Escape::Char(char) => {
+                    char.encode_utf8(&mut data[write_to..]);
+                    write_to += char.len_utf8();
                 }
-                other => panic!(
-                    "only \\n, \\t, \\\\, and \\uXXXX are supported, got: \\{}",
-                    other as char
-                ),
             }
+            read_from += escape_length;
         } else if data[read_from] == b'"' {
             data[write_to] = b'"';
             read_bytesize = read_from + 1 - pos;
             written_bytesize = write_to + 1 - pos;
             break;
+        } else if data[read_from] < 0x20 {
+            // control characters must be escaped
+            return None;
         } else {
-            data[write_to] = data[read_from];
-            read_from += 1;
-            write_to += 1;
+            // copy everything up to the next quote, backslash or control character at once
+            let run = string_run(data, read_from);
+            data.copy_within(read_from..read_from + run, write_to);
+            read_from += run;
+            write_to += run;
         }
     }
 
@@ -93,8 +70,8 @@ fn rewrite_unescaped_json_string(data: &mut [u8], pos: usize) -> Option<Unescapi
 }
 
 impl BitmixToTLV for String {
-    fn bitmix_to_tlv(data: &mut [u8], pos: &mut usize) -> Option<()> {
-        if data[*pos] != b'"' {
+    fn bitmix_to_tlv_with(data: &mut [u8], pos: &mut usize, ctx: &mut Context) -> Option<()> {
+        if data.get(*pos) != Some(&b'"') {
             return None;
         }
         let UnescapingResult {
@@ -102,7 +79,17 @@ impl BitmixToTLV for String {
             written_bytesize,
         } = rewrite_unescaped_json_string(data, *pos)?;
 
-        Length::write(data, *pos, *pos + written_bytesize, written_bytesize - 2);
+        let length = written_bytesize - 2;
+        if length >= MAX_LENGTH || length > ctx.options.max_string_length {
+            return ctx.fail(ParseError::StringTooLong);
+        }
+        if ctx.options.validate_utf8
+            && core::str::from_utf8(&data[*pos + 1..*pos + 1 + length]).is_err()
+        {
+            return ctx.fail(ParseError::InvalidUtf8);
+        }
+
+        Length::write(data, *pos, *pos + written_bytesize, length);
         data[*pos] |= STRING_MASK;
 
         *pos += read_bytesize;
@@ -114,7 +101,7 @@ impl<'a> DecodeTLV<'a> for String {
     type ReturnType = &'a [u8];
 
     fn decode_tlv(data: &'a [u8], pos: &mut usize) -> Option<Self::ReturnType> {
-        if *pos >= data.len() {
+        if *pos + 1 >= data.len() {
             return None;
         }
         if data[*pos] & TYPE_MASK != STRING_MASK {
@@ -122,13 +109,14 @@ impl<'a> DecodeTLV<'a> for String {
         }
 
         let Length(length) = Length::read(data, *pos);
-        let bytes = &data[(*pos + 2)..(*pos + 2 + length)];
+        let bytes = data.get((*pos + 2)..(*pos + 2 + length))?;
         *pos += length + 2;
         Some(bytes)
     }
 }
 
 #[test]
+#[allow(clippy::identity_op)] // the length bits are spelled out
 fn test_string_empty() {
     let mut data = *b" \"\"";
     let mut pos = 1;
@@ -243,3 +231,31 @@ fn test_escaped() {
     assert_eq!(pos, 11);
     assert_eq!(value, b"a\nb\tcd\\e");
 }
+
+#[test]
+fn test_all_escapes() {
+    let mut pos = 1;
+    let mut data = *br#" "\"\\\/\b\f\n\r\t\u00e9\ud83d\ude00""#;
+    String::bitmix_to_tlv(&mut data, &mut pos).unwrap();
+    assert_eq!(pos, data.len());
+
+    pos = 1;
+    let value = String::decode_tlv(&data, &mut pos).unwrap();
+    assert_eq!(value, "\"\\/\u{8}\u{c}\n\r\t\u{e9}\u{1f600}".as_bytes());
+}
+
+#[test]
+fn test_invalid_strings() {
+    for input in [
+        &br#" "abc"#[..],
+        br#" "\x""#,
+        br#" "\u12""#,
+        br#" "\ud800""#,
+        b" \"a\tb\"",
+        b" \"a\\",
+    ] {
+        let mut data = input.to_vec();
+        let mut pos = 1;
+        assert!(String::bitmix_to_tlv(&mut data, &mut pos).is_none());
+    }
+}
@@ -0,0 +1,438 @@
+use crate::{
+    array::Array,
+    length::Length,
+    mask::{ARRAY_MASK, OBJECT_MASK, TYPE_MASK},
+    object::Object,
+    skip_zeroes::skip_zeroes,
+    string::String,
+    tlv::DecodeTLV,
+    validate::MAX_DEPTH,
+    value::Value,
+};
+
+//
+// Tape is an index of the containers of a TLV buffer, every container gets a record:
+//   1. offset of the container in TLV
+//   2. number of elements (or members)
+//   3. index of the first word after the records of the whole subtree
+//   4. end of the container in TLV
+//   5. an entry per element, for objects the offset of the key goes before it
+//
+// An entry is either the offset of a scalar in TLV or, with CONTAINER set,
+// the index of the record of a container. Records of children follow the record of their parent,
+// so reaching any element is O(1) once the record of its container is known.
+// Containers nested deeper than `MAX_DEPTH` levels are rejected, like in `Value::validate_tlv`
+//
+
+const HEADER: usize = 4;
+const CONTAINER: u32 = 1 << 31;
+
+/// Index of the containers of a TLV buffer (i.e. `data` after `Parser::parse`)
+/// in a caller-provided slice, see `Tape::build`.
+#[derive(Debug, Clone, Copy)]
+pub struct Tape<'a> {
+    data: &'a [u8],
+    words: &'a [u32],
+}
+
+// Writes the record of the container at `pos` and the records of its children,
+// returns the end of the container in TLV. Words that don't fit are counted but not written
+fn build_record(
+    data: &[u8],
+    pos: usize,
+    words: &mut [u32],
+    next: &mut usize,
+    depth: usize,
+) -> Option<usize> {
+    if depth >= MAX_DEPTH {
+        return None;
+    }
+    data.get(pos + 1)?;
+    let record = *next;
+    let Length(length) = Length::read(data, pos);
+    let object = data[pos] & TYPE_MASK == OBJECT_MASK;
+    let stride = if object { 2 } else { 1 };
+    *next = record.checked_add(HEADER + length * stride)?;
+    put(words, record, pos as u32);
+    put(words, record + 1, length as u32);
+
+    let mut pos = pos + 2;
+    for idx in 0..length {
+        let mut slot = record + HEADER + idx * stride;
+        skip_zeroes(data, &mut pos);
+        if object {
+            put(words, slot, pos as u32);
+            slot += 1;
+            if !String::skip_tlv(data, &mut pos) {
+                return None;
+            }
+            skip_zeroes(data, &mut pos);
+        }
+
+        if matches!(*data.get(pos)? & TYPE_MASK, ARRAY_MASK | OBJECT_MASK) {
+            if *next >= CONTAINER as usize {
+                return None;
+            }
+            put(words, slot, *next as u32 | CONTAINER);
+            pos = build_record(data, pos, words, next, depth + 1)?;
+        } else {
+            put(words, slot, pos as u32);
+            if !Value::skip_tlv(data, &mut pos) {
+                return None;
+            }
+        }
+    }
+
+    put(words, record + 2, *next as u32);
+    put(words, record + 3, pos as u32);
+    Some(pos)
+}
+
+fn put(words: &mut [u32], idx: usize, word: u32) {
+    if let Some(slot) = words.get_mut(idx) {
+        *slot = word;
+    }
+}
+
+// Runs the build, returns the number of words
+fn build(data: &[u8], words: &mut [u32]) -> Option<usize> {
+    if data.len() >= CONTAINER as usize {
+        return None;
+    }
+    let mut pos = 0;
+    skip_zeroes(data, &mut pos);
+    let mut next = 0;
+    if matches!(*data.get(pos)? & TYPE_MASK, ARRAY_MASK | OBJECT_MASK) {
+        build_record(data, pos, words, &mut next, 0)?;
+    } else if !Value::skip_tlv(data, &mut { pos }) {
+        return None;
+    }
+    Some(next)
+}
+
+impl<'a> Tape<'a> {
+    /// Number of words `Tape::build` needs for `data`,
+    /// `None` if `data` is not TLV or is longer than 2 GiB.
+    pub fn required_len(data: &[u8]) -> Option<usize> {
+        build(data, &mut [])
+    }
+
+    /// Indexes `data` in a single pass, `None` if `tape` is shorter than `Tape::required_len`
+    /// or `data` is not TLV.
+    pub fn build(data: &'a [u8], tape: &'a mut [u32]) -> Option<Self> {
+        let len = build(data, tape)?;
+        let tape: &'a [u32] = tape;
+        let words = tape.get(..len)?;
+        Some(Self { data, words })
+    }
+
+    pub fn root(&self) -> TapeNode<'a> {
+        let mut pos = 0;
+        skip_zeroes(self.data, &mut pos);
+        let record = if self.words.is_empty() { None } else { Some(0) };
+        TapeNode {
+            tape: *self,
+            pos,
+            record,
+            key: None,
+        }
+    }
+
+    fn node(&self, entry: u32, key: Option<usize>) -> TapeNode<'a> {
+        if entry & CONTAINER == 0 {
+            TapeNode {
+                tape: *self,
+                pos: entry as usize,
+                record: None,
+                key,
+            }
+        } else {
+            let record = (entry & !CONTAINER) as usize;
+            TapeNode {
+                tape: *self,
+                pos: self.words[record] as usize,
+                record: Some(record),
+                key,
+            }
+        }
+    }
+}
+
+/// A value in a `Tape`, its elements are accessed in O(1) by index, from either end,
+/// and so are the length and the extent of containers.
+#[derive(Debug, Clone, Copy)]
+pub struct TapeNode<'a> {
+    tape: Tape<'a>,
+    // offset of the value in TLV
+    pos: usize,
+    // index of the record for containers
+    record: Option<usize>,
+    // offset of the key for members of objects
+    key: Option<usize>,
+}
+
+impl<'a> TapeNode<'a> {
+    /// The value, containers are made from their record without going through their data.
+    pub fn value(&self) -> Option<Value<'a>> {
+        let Some(record) = self.record else {
+            return Value::decode_tlv(self.tape.data, &mut { self.pos });
+        };
+        let data = self
+            .tape
+            .data
+            .get(self.pos + 2..self.tape.words[record + 3] as usize)?;
+        let length = self.len();
+        Some(if self.is_object() {
+            Value::Object(Object {
+                data,
+                length,
+                offset: None,
+            })
+        } else {
+            Value::Array(Array {
+                data,
+                length,
+                offset: None,
+            })
+        })
+    }
+
+    /// Key of the value if it's a member of an object.
+    pub fn key(&self) -> Option<&'a [u8]> {
+        String::decode_tlv(self.tape.data, &mut self.key?)
+    }
+
+    /// Position of the value in the TLV buffer.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    fn is_object(&self) -> bool {
+        self.tape.data[self.pos] & TYPE_MASK == OBJECT_MASK
+    }
+
+    /// Number of elements of an array or members of an object, 0 for scalars.
+    pub fn len(&self) -> usize {
+        self.record
+            .map_or(0, |record| self.tape.words[record + 1] as usize)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Element of an array (or member of an object) by its index.
+    pub fn at(&self, index: usize) -> Option<TapeNode<'a>> {
+        if index >= self.len() {
+            return None;
+        }
+        let record = self.record? + HEADER;
+        let words = self.tape.words;
+        Some(if self.is_object() {
+            let slot = record + index * 2;
+            self.tape.node(words[slot + 1], Some(words[slot] as usize))
+        } else {
+            self.tape.node(words[record + index], None)
+        })
+    }
+
+    /// Member of an object by its key, the first one if there are duplicates.
+    /// Keys are compared one by one, but values are never skipped.
+    /// The tape has no hash of the keys, `ObjectIndex` finds members in O(1).
+    pub fn get(&self, key: &str) -> Option<TapeNode<'a>> {
+        if !self.is_object() {
+            return None;
+        }
+        self.iter()
+            .find(|member| member.key() == Some(key.as_bytes()))
+    }
+
+    /// Elements of an array or members of an object, in both directions.
+    pub fn iter(&self) -> TapeIter<'a> {
+        TapeIter {
+            node: *self,
+            front: 0,
+            back: self.len(),
+        }
+    }
+
+    /// Index of the first word after the records of the subtree of this value,
+    /// i.e. how much of the tape it takes.
+    pub fn tape_end(&self) -> usize {
+        self.record
+            .map_or(0, |record| self.tape.words[record + 2] as usize)
+    }
+}
+
+pub struct TapeIter<'a> {
+    node: TapeNode<'a>,
+    front: usize,
+    back: usize,
+}
+
+impl<'a> Iterator for TapeIter<'a> {
+    type Item = TapeNode<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+        self.front += 1;
+        self.node.at(self.front - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for TapeIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.node.at(self.back)
+    }
+}
+
+impl ExactSizeIterator for TapeIter<'_> {}
+
+#[test]
+fn test_tape() {
+    use crate::parser::Parser;
+
+    let mut data = *br#"{"a": [1, {"b": "x", "c": [true]}, []], "d": null, "e": {}}"#;
+    Parser::parse(&mut data).unwrap();
+    let len = Tape::required_len(&data).unwrap();
+    // root: 4 + 3 * 2, "a": 4 + 3, its object: 4 + 2 * 2, "c": 4 + 1, []: 4, {}: 4
+    assert_eq!(len, 10 + 7 + 8 + 5 + 4 + 4);
+    assert!(Tape::build(&data, &mut [0; 20]).is_none());
+
+    let mut words = [0; 64];
+    let tape = Tape::build(&data, &mut words).unwrap();
+    let root = tape.root();
+    assert_eq!(root.len(), 3);
+    assert_eq!(root.tape_end(), len);
+
+    let a = root.get("a").unwrap();
+    assert_eq!(a.key(), Some(&b"a"[..]));
+    assert_eq!(a.len(), 3);
+    assert!(matches!(a.at(0).unwrap().value(), Some(Value::Integer(1))));
+    assert!(a.at(2).unwrap().is_empty());
+    assert!(a.at(3).is_none());
+    let Some(Value::Array(array)) = a.value() else {
+        panic!("expected an array")
+    };
+    assert_eq!(array.len(), 3);
+    assert!(matches!(array.iter().nth(2), Some(Value::Array(inner)) if inner.is_empty()));
+
+    let c = a.at(1).unwrap().get("c").unwrap();
+    assert!(matches!(c.at(0).unwrap().value(), Some(Value::True)));
+    assert!(c.get("x").is_none());
+
+    let keys = root.iter().rev().map(|member| member.key().unwrap());
+    assert!(keys.eq([&b"e"[..], b"d", b"a"]));
+    assert!(matches!(root.at(1).unwrap().value(), Some(Value::Null)));
+    assert_eq!(root.iter().len(), 3);
+    assert_eq!(
+        root.get("e").unwrap().offset(),
+        root.iter().next_back().unwrap().offset()
+    );
+}
+
+#[test]
+fn test_tape_large_array() {
+    extern crate std;
+    use crate::parser::Parser;
+    use std::{format, vec::Vec};
+
+    let mut data = Vec::from(&b"["[..]);
+    for idx in 0..5000 {
+        data.extend_from_slice(format!("{},", idx).as_bytes());
+    }
+    data.extend_from_slice(b"{\"last\": true}]");
+    Parser::parse(&mut data).unwrap();
+
+    let mut words = std::vec![0; Tape::required_len(&data).unwrap()];
+    let tape = Tape::build(&data, &mut words).unwrap();
+    let root = tape.root();
+    assert_eq!(root.len(), 5001);
+    assert!(matches!(
+        root.at(4321).unwrap().value(),
+        Some(Value::Integer(4321))
+    ));
+    let last = root.iter().next_back().unwrap();
+    assert!(matches!(
+        last.get("last").unwrap().value(),
+        Some(Value::True)
+    ));
+    assert!(root
+        .iter()
+        .rev()
+        .skip(1)
+        .zip((0..5000).rev())
+        .all(|(node, idx)| matches!(node.value(), Some(Value::Integer(int)) if int == idx)));
+}
+
+#[test]
+fn test_tape_scalar_root() {
+    use crate::parser::Parser;
+
+    let mut data = *b" \"x\"";
+    Parser::parse(&mut data).unwrap();
+    assert_eq!(Tape::required_len(&data), Some(0));
+    let tape = Tape::build(&data, &mut []).unwrap();
+    let root = tape.root();
+    assert!(matches!(root.value(), Some(Value::String(b"x"))));
+    assert!(root.is_empty() && root.at(0).is_none() && root.get("x").is_none());
+
+    assert!(Tape::required_len(&[]).is_none());
+}
+
+#[test]
+fn test_tape_garbage() {
+    use crate::mask::{NUMBER_MASK, STRING_MASK};
+
+    // truncated headers, lengths past the end and invalid scalars
+    for data in [
+        &[OBJECT_MASK][..],
+        &[ARRAY_MASK | 1],
+        &[ARRAY_MASK | 2, 0, NUMBER_MASK],
+        &[ARRAY_MASK | 1, 0, NUMBER_MASK | 0b1111],
+        &[OBJECT_MASK | 1, 0, STRING_MASK | 5, 0, b'a'],
+        &[OBJECT_MASK | 1, 0, STRING_MASK, 0],
+    ] {
+        assert_eq!(Tape::required_len(data), None);
+        assert!(Tape::build(data, &mut [0; 16]).is_none());
+    }
+
+    let mut deep = [0; 2 * MAX_DEPTH + 2];
+    for pair in deep.chunks_mut(2) {
+        pair[0] = ARRAY_MASK | 1;
+    }
+    deep[2 * MAX_DEPTH] = ARRAY_MASK;
+    assert_eq!(Tape::required_len(&deep), None);
+    assert!(Tape::required_len(&deep[2..]).is_some());
+
+    // anything else is either indexed or rejected, without panicking
+    let mut seed = 1_u32;
+    for _ in 0..10_000 {
+        let mut data = [0; 12];
+        for byte in data.iter_mut() {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            *byte = (seed >> 16) as u8;
+        }
+        let len = data[0] as usize % data.len();
+        if let Some(words) = Tape::required_len(&data[..len]) {
+            let mut tape = [0; 64];
+            if let Some(tape) = tape.get_mut(..words) {
+                let root = Tape::build(&data[..len], tape).unwrap().root();
+                for node in root.iter().rev() {
+                    let _ = node.value();
+                }
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@ extern crate std;
 
 use std::{fmt::Write, string::String};
 
-use crate::{value::Value, Parser};
+use crate::{object::Object, value::Value, Parser};
 
 #[test]
 fn test_parser() {
@@ -72,3 +72,11 @@ fn visit_and_encode(something: Value, out: &mut String) -> Result<(), std::fmt::
 
     Ok(())
 }
+
+// Parses `data`, which has to hold an object
+pub(crate) fn parse_object(data: &mut [u8]) -> Object<'_> {
+    match Parser::parse(data) {
+        Some(Value::Object(object)) => object,
+        _ => panic!("expected an object"),
+    }
+}
@@ -1,5 +1,14 @@
+use crate::{
+    length::{Length, MAX_LENGTH},
+    options::{Context, LENIENT},
+};
+
 pub(crate) trait BitmixToTLV {
-    fn bitmix_to_tlv(data: &mut [u8], pos: &mut usize) -> Option<()>;
+    fn bitmix_to_tlv(data: &mut [u8], pos: &mut usize) -> Option<()> {
+        Self::bitmix_to_tlv_with(data, pos, &mut Context::new(&LENIENT, &mut []))
+    }
+
+    fn bitmix_to_tlv_with(data: &mut [u8], pos: &mut usize, ctx: &mut Context) -> Option<()>;
 }
 
 pub(crate) trait DecodeTLV<'a> {
@@ -14,7 +23,7 @@ pub(crate) trait DecodeTLV<'a> {
 }
 
 pub(crate) fn bitmix_consume_byte<const B: u8>(data: &mut [u8], pos: &mut usize) -> bool {
-    if data[*pos] == B {
+    if data.get(*pos) == Some(&B) {
         data[*pos] = 0;
         *pos += 1;
         true
@@ -22,3 +31,22 @@ pub(crate) fn bitmix_consume_byte<const B: u8>(data: &mut [u8], pos: &mut usize)
         false
     }
 }
+
+// Writes the header of a container whose brackets are at `start` and `end - 1`,
+// `None` if it has more elements than the header can hold
+pub(crate) fn bitmix_close_container(
+    data: &mut [u8],
+    start: usize,
+    end: usize,
+    length: usize,
+    mask: u8,
+) -> Option<()> {
+    if length >= MAX_LENGTH {
+        return None;
+    }
+    data[start] = 0;
+    data[end - 1] = 0;
+    Length::write(data, start, end, length);
+    data[start] |= mask;
+    Some(())
+}
@@ -0,0 +1,287 @@
+use crate::{
+    lex::{literal, number_length, skip_ws, string_end},
+    number::is_valid_number,
+    stack::Stack,
+    true_false_null::TrueFalseNull,
+    validate::MAX_DEPTH,
+};
+
+/// Event produced by `Tokenizer`, strings and numbers are raw slices
+/// of the input (strings without the surrounding quotes, escapes are kept).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event<'a> {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    Key(&'a [u8]),
+    String(&'a [u8]),
+    Number(&'a [u8]),
+    Bool(bool),
+    Null,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Token<'a> {
+    pub event: Event<'a>,
+    /// Offset of the first byte of the token in the input
+    pub pos: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenizeError {
+    pub pos: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    Value,
+    ValueOrEnd,
+    Key,
+    KeyOrEnd,
+    CommaOrEnd,
+    Done,
+    Failed,
+}
+
+/// Pull-based tokenizer that follows the grammar of `Parser::parse`
+/// but leaves the input untouched. Unlike `Parser::parse` it reports
+/// anything but whitespace after the root value as an error.
+pub struct Tokenizer<'a> {
+    data: &'a [u8],
+    pos: usize,
+    expect: Expect,
+    // `true` for objects
+    containers: Stack<bool, MAX_DEPTH>,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            expect: Expect::Value,
+            containers: Stack::new(),
+        }
+    }
+
+    /// Offset of the first byte that hasn't been tokenized yet.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn fail(&mut self, pos: usize) -> Option<Result<Token<'a>, TokenizeError>> {
+        self.expect = Expect::Failed;
+        Some(Err(TokenizeError { pos }))
+    }
+
+    fn emit(event: Event<'a>, pos: usize) -> Option<Result<Token<'a>, TokenizeError>> {
+        Some(Ok(Token { event, pos }))
+    }
+
+    fn value_done(&mut self) {
+        self.expect = match self.containers.last_mut() {
+            Some(_) => Expect::CommaOrEnd,
+            None => Expect::Done,
+        };
+    }
+
+    fn close(&mut self, object: bool) -> Option<Result<Token<'a>, TokenizeError>> {
+        let pos = self.pos;
+        if self.containers.pop() != Some(object) {
+            return self.fail(pos);
+        }
+        self.pos += 1;
+        self.value_done();
+        let event = if object {
+            Event::EndObject
+        } else {
+            Event::EndArray
+        };
+        Self::emit(event, pos)
+    }
+
+    fn string(&mut self) -> Option<&'a [u8]> {
+        let end = string_end(self.data, self.pos)?;
+        let string = &self.data[self.pos + 1..end - 1];
+        self.pos = end;
+        Some(string)
+    }
+
+    fn value(&mut self) -> Option<Result<Token<'a>, TokenizeError>> {
+        let pos = self.pos;
+        let event = match self.data[pos] {
+            b'{' | b'[' => {
+                let object = self.data[pos] == b'{';
+                if self.containers.push(object).is_none() {
+                    return self.fail(pos);
+                }
+                self.pos += 1;
+                if object {
+                    self.expect = Expect::KeyOrEnd;
+                    return Self::emit(Event::StartObject, pos);
+                } else {
+                    self.expect = Expect::ValueOrEnd;
+                    return Self::emit(Event::StartArray, pos);
+                }
+            }
+            b'"' => match self.string() {
+                Some(string) => Event::String(string),
+                None => return self.fail(pos),
+            },
+            _ => {
+                if let Some((literal, length)) = literal(self.data, pos) {
+                    self.pos += length;
+                    match literal {
+                        TrueFalseNull::True => Event::Bool(true),
+                        TrueFalseNull::False => Event::Bool(false),
+                        TrueFalseNull::Null => Event::Null,
+                    }
+                } else {
+                    let length = number_length(self.data, pos);
+                    let number = &self.data[pos..pos + length];
+                    if !is_valid_number(number) {
+                        return self.fail(pos);
+                    }
+                    self.pos += length;
+                    Event::Number(number)
+                }
+            }
+        };
+        self.value_done();
+        Self::emit(event, pos)
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Token<'a>, TokenizeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.expect == Expect::Failed {
+                return None;
+            }
+            skip_ws(self.data, &mut self.pos);
+            let pos = self.pos;
+
+            let Some(&byte) = self.data.get(pos) else {
+                if self.expect == Expect::Done {
+                    return None;
+                }
+                return self.fail(pos);
+            };
+
+            match (self.expect, byte) {
+                (Expect::Value, _) => return self.value(),
+                (Expect::ValueOrEnd, b']') => return self.close(false),
+                (Expect::ValueOrEnd, _) => return self.value(),
+                (Expect::KeyOrEnd, b'}') => return self.close(true),
+                (Expect::Key | Expect::KeyOrEnd, b'"') => {
+                    let Some(key) = self.string() else {
+                        return self.fail(pos);
+                    };
+                    skip_ws(self.data, &mut self.pos);
+                    if self.data.get(self.pos) != Some(&b':') {
+                        return self.fail(self.pos);
+                    }
+                    self.pos += 1;
+                    self.expect = Expect::Value;
+                    return Self::emit(Event::Key(key), pos);
+                }
+                (Expect::CommaOrEnd, b',') => {
+                    self.pos += 1;
+                    self.expect = match self.containers.last_mut() {
+                        Some(true) => Expect::Key,
+                        _ => Expect::Value,
+                    };
+                }
+                (Expect::CommaOrEnd, b'}') => return self.close(true),
+                (Expect::CommaOrEnd, b']') => return self.close(false),
+                _ => return self.fail(pos),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn tokenize(data: &[u8]) -> Result<usize, TokenizeError> {
+    Tokenizer::new(data).try_fold(0, |count, token| token.map(|_| count + 1))
+}
+
+#[test]
+fn test_tokenizer_events() {
+    let data = br#" {"a": [1, -2.5e3, "x\"y"], "b": {}, "c": [true, false, null]} "#;
+    let events = [
+        (Event::StartObject, 1),
+        (Event::Key(b"a"), 2),
+        (Event::StartArray, 7),
+        (Event::Number(b"1"), 8),
+        (Event::Number(b"-2.5e3"), 11),
+        (Event::String(br#"x\"y"#), 19),
+        (Event::EndArray, 25),
+        (Event::Key(b"b"), 28),
+        (Event::StartObject, 33),
+        (Event::EndObject, 34),
+        (Event::Key(b"c"), 37),
+        (Event::StartArray, 42),
+        (Event::Bool(true), 43),
+        (Event::Bool(false), 49),
+        (Event::Null, 56),
+        (Event::EndArray, 60),
+        (Event::EndObject, 61),
+    ]
+    .map(|(event, pos)| Ok(Token { event, pos }));
+
+    assert!(Tokenizer::new(data).eq(events));
+}
+
+#[test]
+fn test_tokenizer_leaves_input_untouched() {
+    let data = *br#"[ "a\n", 1 ]"#;
+    let copy = data;
+    assert_eq!(tokenize(&data), Ok(4));
+    assert_eq!(data, copy);
+}
+
+#[test]
+fn test_tokenizer_errors() {
+    for (input, pos) in [
+        (&b""[..], 0),
+        (b"[1 2]", 3),
+        (b"[1,]", 3),
+        (b"{\"a\" 1}", 5),
+        (b"{\"a\": 1,}", 8),
+        (b"{1: 2}", 1),
+        (b"[}", 1),
+        (b"[1", 2),
+        (b"01", 0),
+        (b"\"a", 0),
+        (b"tru", 0),
+        (b"1 2", 2),
+    ] {
+        assert_eq!(tokenize(input), Err(TokenizeError { pos }), "{:?}", input);
+    }
+}
+
+#[test]
+fn test_tokenizer_agrees_with_parser() {
+    use crate::Parser;
+
+    for input in [
+        &br#"{"a": [1, 2, {"b": null}], "c": "\u0041"}"#[..],
+        b"[1 2]",
+        b"[1,]",
+        b"{\"a\": 1 \"b\": 2}",
+        b"\"\\q\"",
+        b"[[[]]]",
+        b"[-]",
+    ] {
+        let mut data = input.to_vec();
+        assert_eq!(
+            tokenize(input).is_ok(),
+            Parser::parse(&mut data).is_some(),
+            "{:?}",
+            input
+        );
+    }
+}
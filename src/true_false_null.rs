@@ -1,9 +1,11 @@
 use crate::{
+    lex::literal,
     mask::{FALSE_MASK, NULL_MASK, TRUE_MASK},
+    options::Context,
     tlv::{BitmixToTLV, DecodeTLV},
 };
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) enum TrueFalseNull {
     True,
     False,
@@ -11,18 +13,12 @@ pub(crate) enum TrueFalseNull {
 }
 
 impl BitmixToTLV for TrueFalseNull {
-    fn bitmix_to_tlv(data: &mut [u8], pos: &mut usize) -> Option<()> {
-        let region_size = if data.get(*pos..*pos + 4) == Some(b"true") {
-            data[*pos] = TRUE_MASK;
-            4
-        } else if data.get(*pos..*pos + 5) == Some(b"false") {
-            data[*pos] = FALSE_MASK;
-            5
-        } else if data.get(*pos..*pos + 4) == Some(b"null") {
-            data[*pos] = NULL_MASK;
-            4
-        } else {
-            return None;
+    fn bitmix_to_tlv_with(data: &mut [u8], pos: &mut usize, _ctx: &mut Context) -> Option<()> {
+        let (value, region_size) = literal(data, *pos)?;
+        data[*pos] = match value {
+            Self::True => TRUE_MASK,
+            Self::False => FALSE_MASK,
+            Self::Null => NULL_MASK,
         };
 
         // nullify the rest
@@ -43,21 +39,22 @@ impl DecodeTLV<'_> for TrueFalseNull {
         if *pos >= data.len() {
             return None;
         }
-        match data[*pos] {
-            TRUE_MASK => {
-                *pos += 4;
-                Some(Self::True)
-            }
-            FALSE_MASK => {
-                *pos += 5;
-                Some(Self::False)
-            }
-            NULL_MASK => {
-                *pos += 4;
-                Some(Self::Null)
+        let (value, region_size) = match data[*pos] {
+            TRUE_MASK => (Self::True, 4),
+            FALSE_MASK => (Self::False, 5),
+            NULL_MASK => (Self::Null, 4),
+            _ => return None,
+        };
+        *pos += 1;
+
+        // the rest of the region is zeroed, unless it was compacted away
+        for _ in 1..region_size {
+            if data.get(*pos) != Some(&0) {
+                break;
             }
-            _ => None,
+            *pos += 1;
         }
+        Some(value)
     }
 }
 
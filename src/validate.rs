@@ -0,0 +1,179 @@
+use crate::{
+    length::Length,
+    mask::{
+        ARRAY_MASK, FALSE_MASK, NULL_MASK, NUMBER_MASK, OBJECT_MASK, STRING_MASK, TRUE_MASK,
+        TYPE_MASK,
+    },
+    number::Number,
+    skip_zeroes::skip_zeroes,
+    string::String,
+    tlv::DecodeTLV,
+    true_false_null::TrueFalseNull,
+    value::Value,
+};
+
+// Validation recurses into nested containers, this is how deep it's allowed to go
+pub(crate) const MAX_DEPTH: usize = 1024;
+
+/// Describes why a TLV buffer can't be decoded, `pos` is an offset in the validated buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlvError {
+    UnexpectedEnd { pos: usize },
+    InvalidTag { pos: usize },
+    InvalidNumber { pos: usize },
+    ExpectedKey { pos: usize },
+    TooDeep { pos: usize },
+}
+
+pub(crate) fn validate_value(data: &[u8], pos: &mut usize, depth: usize) -> Result<(), TlvError> {
+    skip_zeroes(data, pos);
+    let at = *pos;
+    if at >= data.len() {
+        return Err(TlvError::UnexpectedEnd { pos: at });
+    }
+
+    match data[at] & TYPE_MASK {
+        NUMBER_MASK => Number::decode_tlv(data, pos)
+            .map(|_| ())
+            .ok_or(TlvError::InvalidNumber { pos: at }),
+        STRING_MASK => validate_string(data, pos),
+        ARRAY_MASK | OBJECT_MASK => {
+            if depth >= MAX_DEPTH {
+                return Err(TlvError::TooDeep { pos: at });
+            }
+            if at + 1 >= data.len() {
+                return Err(TlvError::UnexpectedEnd { pos: at });
+            }
+            let is_object = data[at] & TYPE_MASK == OBJECT_MASK;
+            let Length(length) = Length::read(data, at);
+            *pos += 2;
+
+            for _ in 0..length {
+                if is_object {
+                    validate_key(data, pos)?;
+                }
+                validate_value(data, pos, depth + 1)?;
+            }
+            Ok(())
+        }
+        _ if matches!(data[at], NULL_MASK | TRUE_MASK | FALSE_MASK) => {
+            TrueFalseNull::decode_tlv(data, pos)
+                .map(|_| ())
+                .ok_or(TlvError::UnexpectedEnd { pos: at })
+        }
+        _ => Err(TlvError::InvalidTag { pos: at }),
+    }
+}
+
+pub(crate) fn validate_key(data: &[u8], pos: &mut usize) -> Result<(), TlvError> {
+    skip_zeroes(data, pos);
+    if data.get(*pos).map(|byte| byte & TYPE_MASK) != Some(STRING_MASK) {
+        return Err(TlvError::ExpectedKey { pos: *pos });
+    }
+    validate_string(data, pos)
+}
+
+fn validate_string(data: &[u8], pos: &mut usize) -> Result<(), TlvError> {
+    let at = *pos;
+    String::decode_tlv(data, pos)
+        .map(|_| ())
+        .ok_or(TlvError::UnexpectedEnd { pos: at })
+}
+
+impl<'a> Value<'a> {
+    /// Checks that `data` holds a well-formed TLV value that can be decoded without panicking.
+    pub fn validate_tlv(data: &[u8]) -> Result<(), TlvError> {
+        let mut pos = 0;
+        validate_value(data, &mut pos, 0)
+    }
+
+    /// Same as `from_tlv`, but validates untrusted `data` first.
+    pub fn try_from_tlv(data: &'a [u8]) -> Result<Self, TlvError> {
+        Self::validate_tlv(data)?;
+        let mut pos = 0;
+        Self::decode_tlv(data, &mut pos).ok_or(TlvError::InvalidTag { pos: 0 })
+    }
+}
+
+#[test]
+fn test_validate_parsed() {
+    use crate::Parser;
+
+    let mut data = *br#"{"a": [1, -2.5e3, "x\ny"], "b": {"c": null}, "d": true, "e": false}"#;
+    Parser::parse(&mut data).unwrap();
+    assert_eq!(Value::validate_tlv(&data), Ok(()));
+    assert!(Value::try_from_tlv(&data).is_ok());
+}
+
+#[test]
+fn test_validate_truncated() {
+    use crate::Parser;
+
+    let mut data = *br#"{"key": "value", "list": [1, 2, 3]}"#;
+    Parser::parse(&mut data).unwrap();
+    for length in 0..data.len() - 1 {
+        assert!(
+            Value::try_from_tlv(&data[..length]).is_err(),
+            "truncated at {}",
+            length
+        );
+    }
+}
+
+#[test]
+fn test_validate_corrupted() {
+    // string claims 5 bytes, only 2 are there
+    let data = [STRING_MASK | 5, 0, b'a', b'b'];
+    assert_eq!(
+        Value::validate_tlv(&data),
+        Err(TlvError::UnexpectedEnd { pos: 0 })
+    );
+
+    // unknown tag
+    let data = [0b000_00001];
+    assert_eq!(
+        Value::validate_tlv(&data),
+        Err(TlvError::InvalidTag { pos: 0 })
+    );
+
+    // literal with garbage in the length bits
+    let data = [TRUE_MASK | 1, 0, 0, 0];
+    assert_eq!(
+        Value::validate_tlv(&data),
+        Err(TlvError::InvalidTag { pos: 0 })
+    );
+
+    // single-byte number with a non-digit nibble
+    let data = [ARRAY_MASK | 1, 0, NUMBER_MASK | 0b1110];
+    assert_eq!(
+        Value::validate_tlv(&data),
+        Err(TlvError::InvalidNumber { pos: 2 })
+    );
+
+    // multibyte number that runs out of bytes
+    let data = [NUMBER_MASK | 0b11010, 0b0011_0001];
+    assert_eq!(
+        Value::validate_tlv(&data),
+        Err(TlvError::InvalidNumber { pos: 0 })
+    );
+
+    // object key is not a string
+    let data = [OBJECT_MASK | 1, 0, NUMBER_MASK | 1, NUMBER_MASK | 2];
+    assert_eq!(
+        Value::validate_tlv(&data),
+        Err(TlvError::ExpectedKey { pos: 2 })
+    );
+}
+
+#[test]
+fn test_validate_too_deep() {
+    let mut data = [0; 2 * MAX_DEPTH + 3];
+    for idx in 0..=MAX_DEPTH {
+        data[2 * idx] = ARRAY_MASK | 1;
+    }
+    data[2 * MAX_DEPTH + 2] = NULL_MASK;
+    assert_eq!(
+        Value::validate_tlv(&data),
+        Err(TlvError::TooDeep { pos: 2 * MAX_DEPTH })
+    );
+}
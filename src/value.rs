@@ -2,14 +2,16 @@ use crate::{
     array::Array,
     number::{IntOrFloat, Number},
     object::Object,
+    options::Context,
     skip_zeroes::skip_zeroes,
+    span::{Span, Spans},
     string::String,
     tlv::{BitmixToTLV, DecodeTLV},
     true_false_null::TrueFalseNull,
     ws::skip_ws,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum Value<'a> {
     Object(Object<'a>),
     Array(Array<'a>),
@@ -42,8 +44,8 @@ impl<'a> From<&'a [u8]> for Value<'a> {
 impl From<IntOrFloat> for Value<'_> {
     fn from(int_or_float: IntOrFloat) -> Self {
         match int_or_float {
-            IntOrFloat::Integer { .. } => Value::Integer(int_or_float.unwrap_int()),
-            IntOrFloat::Float { .. } => Value::Float(int_or_float.unwrap_float()),
+            IntOrFloat::Integer(int) => Value::Integer(int),
+            IntOrFloat::Float(float) => Value::Float(float),
         }
     }
 }
@@ -65,14 +67,16 @@ impl From<TrueFalseNull> for Value<'_> {
 }
 
 impl BitmixToTLV for Value<'_> {
-    fn bitmix_to_tlv(data: &mut [u8], pos: &mut usize) -> Option<()> {
+    fn bitmix_to_tlv_with(data: &mut [u8], pos: &mut usize, ctx: &mut Context) -> Option<()> {
         skip_ws(data, pos);
 
-        None.or_else(|| Object::bitmix_to_tlv(data, pos))
-            .or_else(|| Array::bitmix_to_tlv(data, pos))
-            .or_else(|| String::bitmix_to_tlv(data, pos))
-            .or_else(|| Number::bitmix_to_tlv(data, pos))
-            .or_else(|| TrueFalseNull::bitmix_to_tlv(data, pos))
+        ctx.with_span(data, pos, |data, pos, ctx| match data.get(*pos)? {
+            b'{' => Object::bitmix_to_tlv_with(data, pos, ctx),
+            b'[' => Array::bitmix_to_tlv_with(data, pos, ctx),
+            b'"' => String::bitmix_to_tlv_with(data, pos, ctx),
+            b'-' | b'0'..=b'9' => Number::bitmix_to_tlv_with(data, pos, ctx),
+            _ => TrueFalseNull::bitmix_to_tlv_with(data, pos, ctx),
+        })
     }
 }
 
@@ -96,6 +100,20 @@ impl<'a> Value<'a> {
         let value = Self::decode_tlv(data, &mut pos)?;
         Some(value)
     }
+
+    /// Span of an array or an object, looked up in the spans recorded by `Parser::parse_with_spans`.
+    /// Scalars don't carry a position, `ArrayIterator::span` and `ObjectIterator::span` cover them.
+    ///
+    /// Only values from the parsers and their iterators have spans, it's `None` for the ones decoded
+    /// from TLV on its own (`Value::from_tlv`, `Parser::compact`, `Tape`, `Cursor`, `ObjectIndex`, ...)
+    /// because their positions aren't the ones of the input.
+    pub fn span(&self, spans: &Spans) -> Option<Span> {
+        match self {
+            Value::Object(object) => spans.at(object.offset?),
+            Value::Array(array) => spans.at(array.offset?),
+            _ => None,
+        }
+    }
 }
 
 #[test]
@@ -0,0 +1,299 @@
+use crate::{
+    length::Length,
+    mask::{ARRAY_MASK, OBJECT_MASK, TYPE_MASK},
+    skip_zeroes::skip_zeroes,
+    stack::Stack,
+    string::String,
+    tlv::DecodeTLV,
+    validate::MAX_DEPTH,
+    value::Value,
+};
+
+/// Returned from `Visitor` callbacks to tell the walk how to proceed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    /// Don't descend into the container that has just started
+    /// (or the value of the key that has just been visited).
+    SkipSubtree,
+    Stop,
+}
+
+/// Callbacks for `Value::accept`, every callback continues the walk by default.
+pub trait Visitor<'a> {
+    fn object_start(&mut self, _len: usize) -> Control {
+        Control::Continue
+    }
+    fn key(&mut self, _key: &'a [u8]) -> Control {
+        Control::Continue
+    }
+    fn object_end(&mut self) -> Control {
+        Control::Continue
+    }
+    fn array_start(&mut self, _len: usize) -> Control {
+        Control::Continue
+    }
+    fn array_end(&mut self) -> Control {
+        Control::Continue
+    }
+    fn string(&mut self, _string: &'a [u8]) -> Control {
+        Control::Continue
+    }
+    fn integer(&mut self, _integer: i64) -> Control {
+        Control::Continue
+    }
+    fn float(&mut self, _float: f64) -> Control {
+        Control::Continue
+    }
+    fn bool(&mut self, _bool: bool) -> Control {
+        Control::Continue
+    }
+    fn null(&mut self) -> Control {
+        Control::Continue
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Frame {
+    remaining: usize,
+    is_object: bool,
+}
+
+fn visit_scalar<'a, V: Visitor<'a>>(value: &Value<'a>, visitor: &mut V) -> Control {
+    match value {
+        Value::String(string) => visitor.string(string),
+        Value::Integer(integer) => visitor.integer(*integer),
+        Value::Float(float) => visitor.float(*float),
+        Value::True => visitor.bool(true),
+        Value::False => visitor.bool(false),
+        Value::Null => visitor.null(),
+        Value::Object(_) | Value::Array(_) => unreachable!("not a scalar"),
+    }
+}
+
+fn start_container<'a, V: Visitor<'a>>(visitor: &mut V, frame: Frame) -> Control {
+    if frame.is_object {
+        visitor.object_start(frame.remaining)
+    } else {
+        visitor.array_start(frame.remaining)
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Walks the value depth-first without recursion, calling `visitor` for every node.
+    /// Returns `None` if the value is malformed or nested deeper than the walk can track.
+    pub fn accept<V: Visitor<'a>>(&self, visitor: &mut V) -> Option<()> {
+        let (data, root) = match self {
+            Value::Array(array) => (
+                array.data,
+                Frame {
+                    remaining: array.length,
+                    is_object: false,
+                },
+            ),
+            Value::Object(object) => (
+                object.data,
+                Frame {
+                    remaining: object.length,
+                    is_object: true,
+                },
+            ),
+            scalar => {
+                visit_scalar(scalar, visitor);
+                return Some(());
+            }
+        };
+
+        if start_container(visitor, root) != Control::Continue {
+            return Some(());
+        }
+        let mut stack = Stack::<Frame, MAX_DEPTH>::new();
+        stack.push(root)?;
+        let mut pos = 0;
+
+        while let Some(frame) = stack.last_mut() {
+            if frame.remaining == 0 {
+                let is_object = frame.is_object;
+                stack.pop();
+                let control = if is_object {
+                    visitor.object_end()
+                } else {
+                    visitor.array_end()
+                };
+                if control == Control::Stop {
+                    return Some(());
+                }
+                continue;
+            }
+            frame.remaining -= 1;
+            skip_zeroes(data, &mut pos);
+
+            if frame.is_object {
+                let key = String::decode_tlv(data, &mut pos)?;
+                skip_zeroes(data, &mut pos);
+                match visitor.key(key) {
+                    Control::Continue => {}
+                    Control::SkipSubtree => {
+                        if !Value::skip_tlv(data, &mut pos) {
+                            return None;
+                        }
+                        continue;
+                    }
+                    Control::Stop => return Some(()),
+                }
+            }
+
+            let tag = *data.get(pos)? & TYPE_MASK;
+            if tag == ARRAY_MASK || tag == OBJECT_MASK {
+                if pos + 1 >= data.len() {
+                    return None;
+                }
+                let child = Frame {
+                    remaining: Length::read(data, pos).0,
+                    is_object: tag == OBJECT_MASK,
+                };
+                match start_container(visitor, child) {
+                    Control::Continue => {
+                        pos += 2;
+                        stack.push(child)?;
+                    }
+                    Control::SkipSubtree => {
+                        if !Value::skip_tlv(data, &mut pos) {
+                            return None;
+                        }
+                    }
+                    Control::Stop => return Some(()),
+                }
+            } else {
+                let value = Value::decode_tlv(data, &mut pos)?;
+                if visit_scalar(&value, visitor) == Control::Stop {
+                    return Some(());
+                }
+            }
+        }
+
+        Some(())
+    }
+}
+
+#[cfg(test)]
+extern crate std;
+
+#[test]
+fn test_visitor_encode() {
+    use crate::Parser;
+    use std::{fmt::Write, string::String};
+
+    struct Encoder(String);
+
+    impl Encoder {
+        fn emit(&mut self, s: &str) -> Control {
+            self.0.push_str(s);
+            Control::Continue
+        }
+    }
+
+    impl<'a> Visitor<'a> for Encoder {
+        fn object_start(&mut self, len: usize) -> Control {
+            write!(self.0, "{}{{", len).unwrap();
+            Control::Continue
+        }
+        fn key(&mut self, key: &'a [u8]) -> Control {
+            write!(self.0, "{}:", std::str::from_utf8(key).unwrap()).unwrap();
+            Control::Continue
+        }
+        fn object_end(&mut self) -> Control {
+            self.emit("}")
+        }
+        fn array_start(&mut self, len: usize) -> Control {
+            write!(self.0, "{}[", len).unwrap();
+            Control::Continue
+        }
+        fn array_end(&mut self) -> Control {
+            self.emit("]")
+        }
+        fn string(&mut self, string: &'a [u8]) -> Control {
+            write!(self.0, "'{}' ", std::str::from_utf8(string).unwrap()).unwrap();
+            Control::Continue
+        }
+        fn integer(&mut self, integer: i64) -> Control {
+            write!(self.0, "{} ", integer).unwrap();
+            Control::Continue
+        }
+        fn float(&mut self, float: f64) -> Control {
+            write!(self.0, "{} ", float).unwrap();
+            Control::Continue
+        }
+        fn bool(&mut self, bool: bool) -> Control {
+            write!(self.0, "{} ", bool).unwrap();
+            Control::Continue
+        }
+        fn null(&mut self) -> Control {
+            self.emit("null ")
+        }
+    }
+
+    let mut data = *br#"{"a": [1, 2.5, [], {}], "b": {"c": "d", "e": [true, false, null]}}"#;
+    let value = Parser::parse(&mut data).unwrap();
+    let mut encoder = Encoder(String::new());
+    value.accept(&mut encoder).unwrap();
+    assert_eq!(
+        encoder.0,
+        "2{a:4[1 2.5 0[]0{}]b:2{c:'d' e:3[true false null ]}}"
+    );
+
+    let mut encoder = Encoder(String::new());
+    Value::Integer(42).accept(&mut encoder).unwrap();
+    assert_eq!(encoder.0, "42 ");
+}
+
+#[test]
+fn test_visitor_skip_and_stop() {
+    use crate::Parser;
+
+    #[derive(Default)]
+    struct Counter {
+        integers: i64,
+        stop_at: Option<i64>,
+    }
+
+    impl<'a> Visitor<'a> for Counter {
+        fn key(&mut self, key: &'a [u8]) -> Control {
+            if key == b"skipped" {
+                Control::SkipSubtree
+            } else {
+                Control::Continue
+            }
+        }
+        fn array_start(&mut self, len: usize) -> Control {
+            if len > 3 {
+                Control::SkipSubtree
+            } else {
+                Control::Continue
+            }
+        }
+        fn integer(&mut self, integer: i64) -> Control {
+            self.integers += integer;
+            if Some(integer) == self.stop_at {
+                Control::Stop
+            } else {
+                Control::Continue
+            }
+        }
+    }
+
+    let mut data =
+        *br#"{"a": 1, "skipped": {"b": 10}, "c": [100, 200, 300, 400], "d": [1000, 2000]}"#;
+    let value = Parser::parse(&mut data).unwrap();
+
+    let mut counter = Counter::default();
+    value.accept(&mut counter).unwrap();
+    assert_eq!(counter.integers, 3001);
+
+    let mut counter = Counter {
+        stop_at: Some(1000),
+        ..Counter::default()
+    };
+    value.accept(&mut counter).unwrap();
+    assert_eq!(counter.integers, 1001);
+}
@@ -1,11 +1,7 @@
+use crate::simd::ws_run;
+
 pub(crate) fn skip_ws(data: &mut [u8], pos: &mut usize) {
-    while *pos < data.len() {
-        match data[*pos] {
-            b' ' | b'\t' | b'\n' | b'\r' => {
-                data[*pos] = 0;
-                *pos += 1
-            }
-            _ => break,
-        }
-    }
+    let run = ws_run(data, *pos);
+    data[*pos..*pos + run].fill(0);
+    *pos += run;
 }